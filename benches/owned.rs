@@ -0,0 +1,32 @@
+//! Compare taking one item out of an `OwnedMod` with cloning the whole
+//! module. Run with `cargo bench --bench owned`.
+extern crate mair;
+use std::hint::black_box;
+use std::time::Instant;
+use mair::parse::lexer::Lexer;
+use mair::parse::parser::{parse_tts, parse_crate};
+use mair::parse::owned::OwnedMod;
+
+fn bench<F: FnMut()>(name: &str, iters: u32, mut f: F) {
+    let begin = Instant::now();
+    for _ in 0..iters {
+        f();
+    }
+    println!("{:<20} {:>12?}/iter", name, begin.elapsed() / iters);
+}
+
+fn main() {
+    let source = include_str!("../tests/parser_large/real.in");
+    let m = OwnedMod::new(source.to_owned(), |s| {
+        let toks: Vec<_> = Lexer::new(s).map(Result::unwrap).collect();
+        parse_crate(s, parse_tts(s, &toks).unwrap()).0
+    });
+    let n = m.module().items.len();
+    println!("{} items, {} bytes", n, source.len());
+    bench("item", 10000, || {
+        black_box(m.item(black_box(n / 2)));
+    });
+    bench("module clone", 1000, || {
+        black_box(m.module().clone());
+    });
+}
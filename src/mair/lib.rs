@@ -3,6 +3,8 @@
 
 #[macro_use] extern crate lazy_static;
 extern crate regex;
+#[cfg(feature="serde")] #[macro_use] extern crate serde;
+#[cfg(feature="proc-macro2")] extern crate proc_macro2;
 
 pub mod parse;
 pub mod namesp;
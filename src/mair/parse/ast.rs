@@ -1,6 +1,7 @@
 use std::cmp::Eq;
-use std::rc::Rc;
+use std::sync::Arc;
 use super::lexer::TokenKind;
+pub use super::Span;
 pub use super::lexer::{SymbolType, KeywordType};
 
 pub type LocStr<'a> = &'a str;
@@ -9,12 +10,18 @@ pub type LocStr<'a> = &'a str;
 #[allow(non_camel_case_types)]
 pub type imax = i64;
 
+/// Unsigned integer type holding any integer literal.
+#[allow(non_camel_case_types)]
+pub type umax = u128;
+
 /// Floating point type with the maximum width supported.
 #[allow(non_camel_case_types)]
 pub type fmax = f64;
 
 /// A module, a crate, or a rust source file.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct Mod<'a> {
     pub attrs: Vec<Attr<'a>>,
     pub items: Vec<Item<'a>>,
@@ -25,23 +32,45 @@ pub type Item<'a> = ItemWrap<'a, ItemKind<'a>>;
 
 /// An Item with detail type `T`.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a, T: ::serde::Deserialize<'de>")))]
 pub struct ItemWrap<'a, T> {
     pub attrs:  Vec<Attr<'a>>,
-    pub is_pub: bool,
+    pub vis:    Visibility<'a>,
     pub detail: T,
+    /// From the first outer attribute (if any) to the end of the item.
+    pub span:   Span,
+}
+
+/// The visibility of an item or a field.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
+pub enum Visibility<'a> {
+    /// No `pub`.
+    Private,
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(self)`
+    SelfMod,
+    /// `pub(super)`
+    Super,
+    /// `pub(in <path>)`
+    InPath(Path<'a>),
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum ItemKind<'a> {
     // https://doc.rust-lang.org/reference/items.html#items
-    /// `extern` `crate` <name> `;`
-    ExternCrate { name: Ident<'a> },
-    /// `use <path>::*;`
-    UseAll      { path: UsePath<'a> },
-    /// `use <path>::<name> [as <alias>];`
-    UseOne      { path: UsePath<'a>, name: UseName<'a> },
-    /// `use <path>::{<name1> [as <alias1>], ... };`
-    UseSome     { path: UsePath<'a>, names: Vec<UseName<'a>> },
+    /// `extern` `crate` <name> [`as` <alias>] `;`, where `name` may be
+    /// `self`.
+    ExternCrate { name: Ident<'a>, alias: Option<Ident<'a>> },
+    /// `use <tree>;`
+    Use         (UseTree<'a>),
     /// `mod <name>;`
     ExternMod   { name: Ident<'a> },
     /// `mod <name> { <item1> ... }`
@@ -71,6 +100,11 @@ pub enum ItemKind<'a> {
                 , templ:  Template<'a>
                 , whs:    OptWhere<'a>
                 , fields: Vec<StructField<'a>> },
+    /// `union <name> <template> [where_clause] { <field1>, ... }`
+    Union       { name:   Ident<'a>
+                , templ:  Template<'a>
+                , whs:    OptWhere<'a>
+                , fields: Vec<StructField<'a>> },
     /// `enum <name> <template> [where_clause] { <var1>, ... }`
     Enum        { name:  Ident<'a>
                 , templ: Template<'a>
@@ -80,39 +114,70 @@ pub enum ItemKind<'a> {
     Const       { name: Ident<'a>
                 , ty:   Box<Ty<'a>>
                 , val:  Box<Expr<'a>> },
-    /// `static <name>: <ty> = <val>;`
-    Static      { name: Ident<'a>
-                , ty:   Box<Ty<'a>>
-                , val:  Box<Expr<'a>> },
-    /// `trait <name> <template> [where_clause] { <item1> ... }`
-    Trait       { name:  Ident<'a>
-                , templ: Template<'a>
-                , base:  Option<Box<Ty<'a>>>
-                , whs:   OptWhere<'a>
-                , items: Vec<TraitItem<'a>> },
-    /// `impl <template> <ty> [where_clause] { <item1> ... }`
-    ImplType    { templ: Template<'a>
-                , ty:    Box<Ty<'a>>
-                , whs:   OptWhere<'a>
-                , items: Vec<ImplItem<'a>> },
-    /// `impl <template> <tr> for <ty> [where_clause] { <item1> ... }`
-    ImplTrait   { templ: Template<'a>
-                , tr:    Box<Trait<'a>>
-                , ty:    Box<Ty<'a>>
-                , whs:   OptWhere<'a>
-                , items: Vec<ImplItem<'a>> },
+    /// `static [mut] <name>: <ty> = <val>;`
+    Static      { is_mut: bool
+                , name:   Ident<'a>
+                , ty:     Box<Ty<'a>>
+                , val:    Box<Expr<'a>> },
+    /// `[unsafe] [auto] trait <name> <template> [: <bounds>] [where_clause]
+    /// { <item1> ... }`
+    Trait       { is_unsafe: bool
+                , is_auto:   bool
+                , name:      Ident<'a>
+                , templ:     Template<'a>
+                , base:      Option<Vec<Bound<'a>>>
+                , whs:       OptWhere<'a>
+                , items:     Vec<TraitItem<'a>> },
+    /// `[unsafe] impl <template> <ty> [where_clause] { <item1> ... }`
+    ImplType    { is_unsafe: bool
+                , templ:     Template<'a>
+                , ty:        Box<Ty<'a>>
+                , whs:       OptWhere<'a>
+                , items:     Vec<ImplItem<'a>> },
+    /// `[unsafe] impl <template> [const] [!] <tr> for <ty> [where_clause]
+    /// { <item1> ... }`
+    ImplTrait   { is_unsafe: bool
+                , templ:     Template<'a>
+                , is_const:  bool
+                , is_neg:    bool
+                , tr:        Box<Trait<'a>>
+                , ty:        Box<Ty<'a>>
+                , whs:       OptWhere<'a>
+                , items:     Vec<ImplItem<'a>> },
+    /// `macro_rules! <name> <body>`, the rules in `body` are not parsed.
+    MacroDef    { name: Ident<'a>, body: TT<'a> },
     PluginInvoke(PluginInvoke<'a>),
 }
 
+/// A tree of names in `use`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
+pub enum UseTree<'a> {
+    /// `*`
+    Glob,
+    /// `self` or `<name> [as <alias>]`
+    Name (UseName<'a>),
+    /// `{ <tree1>, ... }`
+    Group(Vec<UseTree<'a>>),
+    /// `<path>::<sub>`. In a nested tree, the path is relative to the parent
+    /// one, like `b::c` in `use a::{b::c, d}`.
+    Path { path: UsePath<'a>, sub: Box<UseTree<'a>> },
+}
+
 /// A path in `use`.
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
-pub enum UsePath<'a> {
-    Absolute{ comps: Vec<Ident<'a>> },
-    Relative{ supers: usize, comps: Vec<Ident<'a>> },
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct UsePath<'a> {
+    pub root:  PathRoot,
+    pub comps: Vec<Ident<'a>>,
 }
 
 /// A single name referred in a `use` declaration.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum UseName<'a> {
     Self_ (LocStr<'a>),
     Name  { name: Ident<'a>, alias: Option<Ident<'a>> },
@@ -122,58 +187,95 @@ pub type ExternItem<'a> = ItemWrap<'a, ExternItemKind<'a>>;
 
 /// An item inside `extern` block.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum ExternItemKind<'a> {
     Func  { name:   Ident<'a>
           , args:   Vec<FuncParam<'a>>
           , is_va:  bool
           , ret_ty: Option<Box<Ty<'a>>> },
-    Static{ name:  Ident<'a>
-          , ty:    Option<Box<Ty<'a>>> }
+    Static{ is_mut: bool
+          , name:   Ident<'a>
+          , ty:     Option<Box<Ty<'a>>> }
 }
 
 pub type TraitItem<'a> = ItemWrap<'a, TraitItemKind<'a>>;
 
 /// An item inside `trait` block.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum TraitItemKind<'a> {
-    AssocTy{ name: Ident<'a>
+    /// `type <name> <template> [: <bounds>] [where_clause]
+    /// [= <default>];`
+    AssocTy{ name:    Ident<'a>
+           , templ:   Template<'a>
+           , bound:   Option<Vec<Bound<'a>>>
+           , whs:     OptWhere<'a>
            , default: Option<Box<Ty<'a>>> },
     Func   { sig:     Box<FuncSig<'a>>
            , default: Option<Box<Expr<'a>>> },
+    /// `const <name>: <ty> [= <default>];`
+    Const  { name:    Ident<'a>
+           , ty:      Box<Ty<'a>>
+           , default: Option<Box<Expr<'a>>> },
 }
 
 pub type ImplItem<'a> = ItemWrap<'a, ImplItemKind<'a>>;
 
 /// An item inside `impl` block.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum ImplItemKind<'a> {
-    AssocTy{ name:  Ident<'a>
-           , val:   Box<Ty<'a>> },
-    Func   { sig:   Box<FuncSig<'a>>
-           , body:  Box<Expr<'a>> },
+    /// `[default] type <name> <template> = <val> [where_clause];`
+    AssocTy{ is_default: bool
+           , name:       Ident<'a>
+           , templ:      Template<'a>
+           , val:        Box<Ty<'a>>
+           , whs:        OptWhere<'a> },
+    /// `[default] fn <sig> <body>`
+    Func   { is_default: bool
+           , sig:        Box<FuncSig<'a>>
+           , body:       Box<Expr<'a>> },
+    /// `[default] const <name>: <ty> = <val>;`
+    Const  { is_default: bool
+           , name:       Ident<'a>
+           , ty:         Box<Ty<'a>>
+           , val:        Box<Expr<'a>> },
 }
 
 /// An element of a tuple-like struct or enum variant.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct StructTupleElem<'a> {
     pub attrs:  Vec<Attr<'a>>,
-    pub is_pub: bool,
+    pub vis:    Visibility<'a>,
     pub ty:     Ty<'a>,
 }
 
 /// a field of a normal struct or enum variant.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct StructField<'a> {
     pub attrs:  Vec<Attr<'a>>,
-    pub is_pub: bool,
+    pub vis:    Visibility<'a>,
     pub name:   Ident<'a>,
     pub ty:     Ty<'a>,
+    pub span:   Span,
 }
 
 /// An variant of an `enum`.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum EnumVar<'a> {
-    Unit  { attrs: Vec<Attr<'a>>, name: Ident<'a> },
+    /// A unit variant with an optional discriminant, like `A = 1`.
+    Unit  { attrs: Vec<Attr<'a>>
+          , name: Ident<'a>
+          , disc: Option<Box<Expr<'a>>> },
     Tuple { attrs: Vec<Attr<'a>>
           , name: Ident<'a>
           , elems: Vec<StructTupleElem<'a>> },
@@ -182,15 +284,45 @@ pub enum EnumVar<'a> {
           , fields: Vec<StructField<'a>> },
 }
 
-/// A path, like `::std::Option`, `MyEnum::A`, etc.
+/// A path, like `::std::Option`, `MyEnum::A`, `<T as Tr>::Item`, etc.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct Path<'a> {
-    pub is_absolute: bool,
-    pub comps:       Vec<PathComp<'a>>,
+    pub qself: Option<Box<QSelf<'a>>>,
+    pub root:  PathRoot,
+    pub comps: Vec<PathComp<'a>>,
+}
+
+/// The root where a path starts from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub enum PathRoot {
+    /// No explicit root, like `a::b`.
+    None,
+    /// `::`
+    Global,
+    /// `crate::`
+    Crate,
+    /// `super::super:: ...`, with the count of `super`.
+    Super(usize),
+    /// `self::`
+    SelfMod,
+}
+
+/// The qualified self type of a path, `<ty [as tr]>` in `<ty [as tr]>::..`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct QSelf<'a> {
+    pub ty: Ty<'a>,
+    pub tr: Option<TyApply<'a>>,
 }
 
 /// A path component, maybe with template hint (if any).
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum PathComp<'a> {
     Self_  (LocStr<'a>),
     SelfTy_(LocStr<'a>),
@@ -199,18 +331,34 @@ pub enum PathComp<'a> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum TyHintArg<'a> {
     Lifetime(Lifetime<'a>),
     Ty      (Ty<'a>),
+    /// A const argument. See `TyApplyArg::Const`.
+    Const   (Expr<'a>),
 }
 
+/// Template parameters, kept in the order they appear in source.
 pub type Template<'a> = Vec<TemplArg<'a>>;
 
 /// Template type or trait bound.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum TemplArg<'a> {
+    /// `<name> [: <lt1> + <lt2> ...]`, `name` outlives all lifetimes in
+    /// `bound`.
     Lifetime{ name: Lifetime<'a>, bound: Option<Vec<Lifetime<'a>>> },
-    Ty      { name: Ident<'a>, bound: Option<Trait<'a>> },
+    /// `<name> [: <bound>] [= <default>]`
+    Ty      { name:    Ident<'a>
+            , bound:   Option<Vec<Bound<'a>>>
+            , default: Option<Box<Ty<'a>>> },
+    /// `const <name>: <ty> [= <default>]`
+    Const   { name:    Ident<'a>
+            , ty:      Box<Ty<'a>>
+            , default: Option<Box<Expr<'a>>> },
 }
 
 pub type Where<'a> = Vec<Restrict<'a>>;
@@ -218,15 +366,24 @@ pub type OptWhere<'a> = Option<Where<'a>>;
 
 /// A trait bound or lifetime restriction.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Restrict<'a> {
     LifeBound  { lt: Lifetime<'a>, bound: Vec<Lifetime<'a>> },
-    TraitBound { ty: Ty<'a>, bound: Trait<'a> },
+    /// `[for<'a, ..>] <ty>: <bounds>`
+    TraitBound { for_lts: Vec<Lifetime<'a>>
+               , ty:      Ty<'a>
+               , bound:   Vec<Bound<'a>> },
 }
 
 /// The signature of a function, including templates, trait bounds,
 /// argument names and the function type.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct FuncSig<'a> {
+    pub is_const:  bool,
+    pub is_async:  bool,
     pub is_unsafe: bool,
     pub abi:       ABI<'a>,
     pub name:      Ident<'a>,
@@ -235,10 +392,14 @@ pub struct FuncSig<'a> {
     pub is_va:     bool,
     pub ret_ty:    Option<Box<Ty<'a>>>,
     pub whs:       OptWhere<'a>,
+    /// From the first qualifier to the end of the where clause.
+    pub span:      Span,
 }
 
 /// The signature of a lambda function.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct LambdaSig<'a> {
     pub is_move: bool,
     /// The location of capture list including `|`.
@@ -249,16 +410,23 @@ pub struct LambdaSig<'a> {
 
 /// A parameter of a function.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum FuncParam<'a> {
     SelfMove{ is_mut: bool },
     SelfRef { is_mut: bool },
     SelfAs  (Ty<'a>),
+    /// `<pat>: <ty>`, like `mut x: u8` or `(a, b): (i32, i32)`.
     Bind    { pat: Pat<'a>, ty: Box<Ty<'a>> },
 }
 
 /// The type of a function.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct FuncTy<'a> {
+    /// Higher-ranked lifetimes. eg. `'a` in `for<'a> fn(&'a u8)`
+    pub for_lts:   Vec<Lifetime<'a>>,
     pub is_unsafe: bool,
     pub abi:       ABI<'a>,
     pub args:      Vec<FuncTyParam<'a>>,
@@ -267,6 +435,8 @@ pub struct FuncTy<'a> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct FuncTyParam<'a> {
     pub name: Option<Ident<'a>>,
     pub ty:   Ty<'a>,
@@ -274,14 +444,18 @@ pub struct FuncTyParam<'a> {
 
 /// The ABI of a function or an `extern` block.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum ABI<'a> {
     Normal,
     Extern,
-    Specific{ loc: LocStr<'a>, abi: Rc<String> },
+    Specific{ loc: LocStr<'a>, abi: Arc<String> },
 }
 
 /// A type.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Ty<'a> {
     /// The error type. Used in codes with syntax errors.
     Error,
@@ -292,7 +466,11 @@ pub enum Ty<'a> {
     /// The type `Self`.
     Self_,
     /// An unsized type only with trait bounds.
-    Traits (Vec<TyApply<'a>>),
+    Traits (Vec<PolyTrait<'a>>),
+    /// A trait object, like `dyn Error + Send + 'static`.
+    Dyn    { bounds: Vec<PolyTrait<'a>>, lt: Option<Lifetime<'a>> },
+    /// An anonymous type implementing traits, like `impl Iterator<Item=u8>`.
+    Impl   { bounds: Vec<PolyTrait<'a>>, lt: Option<Lifetime<'a>> },
     /// A generic type/trait applied with type paramaters, like `Vec<i32>`,
     /// `Iterator<Item=i32>`.
     /// No type arguments indicates a simple type/trait, like `i32`, `Copy`.
@@ -307,62 +485,112 @@ pub enum Ty<'a> {
            , ty:     Box<Ty<'a>> },
     /// Pointer.
     Ptr    { is_mut: bool, ty: Box<Ty<'a>> },
-    /// Slice.
+    /// Slice, like `[u8]`.
     Slice  (Box<Ty<'a>>),
-    /// Array.
+    /// Array with a constant expression as its size, like `[u8; N * 2]`.
     Array  { ty: Box<Ty<'a>>, size: Box<Expr<'a>> },
     /// The function pointer type, like `fn(i32, u8) -> usize`.
     Func   (Box<FuncTy<'a>>),
+    /// A type given by a macro, like `ty!(u8)`.
+    PluginInvoke(Box<PluginInvoke<'a>>),
 }
 pub type Trait<'a> = Ty<'a>; // Types and traits are the same things at this
                              // time.
 
+/// A trait in bounds, maybe relaxed or with higher-ranked lifetimes.
+/// eg. `?Sized`, `for<'a> Fn(&'a str)`
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
+pub struct PolyTrait<'a> {
+    /// Whether there's a `?` before the trait.
+    pub is_maybe: bool,
+    pub for_lts:  Vec<Lifetime<'a>>,
+    pub tr:       TyApply<'a>,
+}
+
+/// A bound of a type parameter. eg. `Send`, `?Sized`, `'a`
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
+pub enum Bound<'a> {
+    Trait   (PolyTrait<'a>),
+    /// An outlives bound.
+    Lifetime(Lifetime<'a>),
+}
+
 /// A simple type, specialized type or trait.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum TyApply<'a> {
+    /// `<name> [< <arg1>, ... >]`
     Angle { name: Path<'a>, args: Vec<TyApplyArg<'a>> },
+    /// The sugar of `Fn` traits. eg. `Fn(i32) -> i32`
     Paren { name: Path<'a>, args: Vec<Ty<'a>>, ret_ty: Option<Box<Ty<'a>>> },
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum TyApplyArg<'a> {
     Lifetime(Lifetime<'a>),
     Ty      (Ty<'a>),
+    /// A const argument, which is a literal, a negative literal or a block.
+    /// eg. `3` in `Foo<3>`, `{ N + 1 }` in `Matrix<{ N + 1 }>`.
+    Const   (Expr<'a>),
+    /// An associated type binding. eg. `Item = u8` in `Iterator<Item = u8>`
     AssocTy { name: Ident<'a>, ty: Ty<'a> },
 }
 
-/// An attribute.
+/// An attribute. `is_inner` is set for inner ones like `#![no_std]` and
+/// `//! doc`.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Attr<'a> {
-    Doc { loc: LocStr<'a>, doc: &'a str },
-    Meta(Meta<'a>),
+    Doc   { is_inner: bool, loc: LocStr<'a>, doc: &'a str },
+    Meta  { is_inner: bool, meta: Meta<'a> },
+    /// An attribute whose content is not a valid meta, like
+    /// `#[my_attr(a + b, key = [1, 2])]`. `tts` are the TTs after `name`.
+    Tokens{ is_inner: bool, name: Path<'a>, tts: Vec<TT<'a>> },
 }
 
 /// An meta (the content inside `#[]`).
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Meta<'a> {
-    /// A single meta name, like `test`, `macro_use`.
-    Flag    (Ident<'a>),
+    /// A single meta name, like `test`, `macro_use`, `rustfmt::skip`.
+    Flag    (Path<'a>),
     /// A key-value pair, like `crate_type = "lib"`, `recursion_limit="64"`.
-    KeyValue{ key: Ident<'a>, value: Literal<'a> },
+    KeyValue{ key: Path<'a>, value: Literal<'a> },
     /// A meta with a list of sub-meta arguments,
     /// like `cfg(target_os="linux")`.
-    Sub     { name: Ident<'a>, subs: Vec<Meta<'a>> },
+    Sub     { name: Path<'a>, subs: Vec<Meta<'a>> },
 }
 
 /// A statement.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Stmt<'a> {
     Item        (Box<Item<'a>>),
+    /// `let <pat> [: <ty>] [= <init>];`
     Let         { pat:  Pat<'a>
-                , ty:   Box<Ty<'a>>
-                , expr: Box<Expr<'a>> },
+                , ty:   Option<Box<Ty<'a>>>
+                , init: Option<Box<Expr<'a>>> },
+    /// A block-like expression without `;`, like `if a {}`.
     Expr        (Expr<'a>),
+    /// An expression followed by `;`.
+    Semi        (Expr<'a>),
     PluginInvoke(PluginInvoke<'a>),
 }
 
 /// An expression.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Expr<'a> { // https://doc.rust-lang.org/reference/expressions.html
     /// The error expression. Used in codes with syntax errors.
     Error,
@@ -377,19 +605,31 @@ pub enum Expr<'a> { // https://doc.rust-lang.org/reference/expressions.html
                 , stmts: Vec<Stmt<'a>>
                 , ret:   Option<Box<Expr<'a>>> },
     Unsafe      (Box<Expr<'a>>),
+    /// An inline const block. eg. `const { 1 + 2 }`
+    ConstBlock  (Box<Expr<'a>>),
+    /// An async block. eg. `async move { x.await }`
+    Async       { is_move: bool, block: Box<Expr<'a>> },
+    /// A try block. eg. `try { a? + b? }`
+    TryBlock    (Box<Expr<'a>>),
     MemberCall  { obj:  Box<Expr<'a>>
                 , func: PathComp<'a>
                 , par_loc: LocStr<'a>
                 , args: Vec<Expr<'a>> },
     StructField { obj: Box<Expr<'a>>, field: PathComp<'a> },
-    TupleField  { obj: Box<Expr<'a>>, ind_loc: LocStr<'a>, index: imax },
+    /// `<expr>.await`, only in Rust 2018 or later.
+    Await       (Box<Expr<'a>>),
+    TupleField  { obj: Box<Expr<'a>>, ind_loc: LocStr<'a>, index: umax },
     Index       { obj: Box<Expr<'a>>
                 , brk_loc: LocStr<'a>
                 , index: Box<Expr<'a>> },
     ArrayFill   { elem: Box<Expr<'a>>
                 , len: Box<Expr<'a>> },
     ArrayLit    (Vec<Expr<'a>>),
-    // Range is BinaryOp
+    /// `[<start>] (..|..=) [<end>]`
+    Range       { start:        Option<Box<Expr<'a>>>
+                , end:          Option<Box<Expr<'a>>>
+                , is_inclusive: bool
+                , op_loc:       LocStr<'a> },
     UnaryOp     { op: UnaryOp, op_loc: LocStr<'a>, expr: Box<Expr<'a>> },
     As          { expr: Box<Expr<'a>>, kw_loc: LocStr<'a>, ty: Box<Ty<'a>> },
     Colon       { expr: Box<Expr<'a>>, kw_loc: LocStr<'a>, ty: Box<Ty<'a>> },
@@ -411,9 +651,10 @@ pub enum Expr<'a> { // https://doc.rust-lang.org/reference/expressions.html
     While       { label: Option<Lifetime<'a>>
                 , cond: Box<Expr<'a>>
                 , body: Box<Expr<'a>> },
-    WhileLet    { pat:  Box<Pat<'a>>
-                , expr: Box<Expr<'a>>
-                , body: Box<Expr<'a>> },
+    WhileLet    { label: Option<Lifetime<'a>>
+                , pat:   Box<Pat<'a>>
+                , expr:  Box<Expr<'a>>
+                , body:  Box<Expr<'a>> },
     For         { label: Option<Lifetime<'a>>
                 , pat:   Box<Pat<'a>>
                 , iter:  Box<Expr<'a>>
@@ -436,13 +677,18 @@ pub enum Expr<'a> { // https://doc.rust-lang.org/reference/expressions.html
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct ExprStructField<'a> {
+    /// The field name, or the index like `0` of a tuple struct.
     pub name: Ident<'a>,
     pub expr: Option<Box<Expr<'a>>>,
 }
 
 /// A match arm.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct MatchArm<'a> {
     pub pats: Vec<Pat<'a>>,
     pub cond: Option<Box<Expr<'a>>>,
@@ -451,6 +697,8 @@ pub struct MatchArm<'a> {
 
 /// A pattern.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Pat<'a> {
     /// The hole `_`.
     Hole,
@@ -465,14 +713,16 @@ pub enum Pat<'a> {
     Path          (Path<'a>),
     /// An literal. eg. `123`
     Literal       (Literal<'a>),
-    /// A range patterns. eg. `1...2`, `'a'...'z'`
+    /// A range patterns. eg. `1..=2`, `'a'...'z'`
     Range         (Literal<'a>, Literal<'a>),
-    /// A reference.
-    Ref           (Box<Pat<'a>>),
+    /// A reference. eg. `&x`, `&mut (a, b)`
+    Ref           { is_mut: bool, pat: Box<Pat<'a>> },
     /// A tuple. eg. `(_, _)`
     Tuple         (Vec<Pat<'a>>),
     /// A pattern inside (redundant) paren.
     Paren         (Box<Pat<'a>>),
+    /// Alternatives. eg. `Some(1) | None`
+    Or            (Vec<Pat<'a>>),
     /// A tuple-like enum variant or tuple struct. eg. `Some(1)`
     DestructTuple { name: Path<'a>, elems: Vec<Pat<'a>> },
     /// A struct-like enum variant or normal struct. eg. `Pt{ x: xx, y }`
@@ -484,6 +734,8 @@ pub enum Pat<'a> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct DestructField<'a> {
     pub is_ref: bool,
     pub is_mut: bool,
@@ -492,6 +744,7 @@ pub struct DestructField<'a> {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
 pub enum Delimiter {
     /// `()`
     Paren,
@@ -502,16 +755,20 @@ pub enum Delimiter {
 }
 
 /// A plugin(including macro) invocation.
-/// eg. `name! ( tts... )`
+/// eg. `name! ( tts... )`, `std::println!{ tts... }`
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub struct PluginInvoke<'a> {
-    pub name:  Ident<'a>,
+    pub name:  Path<'a>,
     pub ident: Option<Ident<'a>>,
     pub tt:    TT<'a>, // must be TokenTree::Tree
 }
 
 /// A token tree with location.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum TTKind<'a> {
     Token(TokenKind<'a>),
     Tree{ delim: Delimiter, tts: Vec<TT<'a>> },
@@ -520,6 +777,7 @@ pub enum TTKind<'a> {
 pub type TT<'a> = (TTKind<'a>, LocStr<'a>);
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
 pub enum UnaryOp {
     // https://doc.rust-lang.org/grammar.html#unary-operator-expressions
     Try,
@@ -528,14 +786,13 @@ pub enum UnaryOp {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
 pub enum BinaryOp {
     // https://doc.rust-lang.org/grammar.html#binary-operator-expressions
     Add, Sub, Mul, Div, Mod,
     And, Or, Xor, Shl, Shr,
     LogAnd, LogOr,
     Equ, Ne, Lt, Gt, Le, Ge,
-    /// `as` in `Expr`
-    Range, RangeInclusive,
     Place,
     Assign,
     AddAssign, SubAssign, MulAssign, DivAssign, ModAssign,
@@ -544,19 +801,24 @@ pub enum BinaryOp {
 
 /// A literal.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'a")))]
 pub enum Literal<'a> {
     /// A char or byte char.
     CharLike { is_byte: bool, ch: char },
     /// A string, raw string, byte string or raw byte string.
-    StrLike  { is_bytestr: bool, s: Rc<String> },
-    /// An interer type. If it has no type suffix, `ty` is None.
-    IntLike  { ty: Option<Box<Ty<'a>>>, val: imax },
+    StrLike  { is_bytestr: bool, s: Arc<String> },
+    /// An interer type. If it has no type suffix, `ty` is None. `val` is
+    /// never negative, since `-` is a unary operator.
+    IntLike  { ty: Option<Box<Ty<'a>>>, val: umax },
     /// An floating point type. If it has no type suffix, `ty` is None.
     FloatLike{ ty: Option<Box<Ty<'a>>>, val: fmax },
     /// A boolean literal `true` or `false`.
     Bool     (bool),
 }
 
+/// An identifier, or the location where it is missing. A raw identifier keeps
+/// its `r#` prefix, like `r#match`, so it can be printed back.
 pub type Ident<'a> = Result<LocStr<'a>, LocStr<'a>>;
 pub type Lifetime<'a> = &'a str;
 
@@ -572,7 +834,8 @@ impl<'a> Ty<'a> {
 
     pub fn from_name(name: &'a str) -> Self {
         Ty::from_path(Path{
-            is_absolute: false,
+            qself: None,
+            root: PathRoot::None,
             comps: vec![PathComp::Name{
                 name: Ok(name),
                 hint: None,
@@ -585,10 +848,118 @@ impl<'a> Ty<'a> {
     }
 }
 
+impl<'a> PolyTrait<'a> {
+    /// Return whether it is only a trait without `?` or `for<..>`.
+    pub fn is_plain(&self) -> bool {
+        !self.is_maybe && self.for_lts.is_empty()
+    }
+}
+
+impl<'a> Path<'a> {
+    /// Return the name if the path is a single relative identifier without
+    /// template hint, like `foo`.
+    pub fn as_ident(&self) -> Option<Ident<'a>> {
+        match (&self.qself, self.root, self.comps.as_slice()) {
+            (&None, PathRoot::None, &[PathComp::Name{ name, hint: None }]) =>
+                Some(name),
+            _ => None,
+        }
+    }
+
+    /// Return whether the path is the single identifier `name`.
+    pub fn is_ident(&self, name: &str) -> bool {
+        self.as_ident() == Some(Ok(name))
+    }
+}
+
+impl<'a> Attr<'a> {
+    /// Return whether it is an inner attribute, like `#![no_std]`.
+    pub fn is_inner(&self) -> bool {
+        match *self {
+            Attr::Doc{ is_inner, .. } |
+            Attr::Meta{ is_inner, .. } |
+            Attr::Tokens{ is_inner, .. } => is_inner,
+        }
+    }
+
+    /// Return the document of `/// ..`, `/** .. */`, their inner forms, or
+    /// `#[doc = ".."]`.
+    pub fn doc(&self) -> Option<&str> {
+        match *self {
+            Attr::Doc{ doc, .. } => Some(doc),
+            Attr::Meta{ meta: Meta::KeyValue{
+                ref key,
+                value: Literal::StrLike{ is_bytestr: false, ref s },
+            }, .. } if key.is_ident("doc") => Some(s),
+            _ => None,
+        }
+    }
+
+    /// If it is `#[cfg_attr(<pred>, <meta>..)]`, return the predicate and
+    /// the metas to apply when it holds.
+    pub fn as_cfg_attr(&self) -> Option<(&Meta<'a>, &[Meta<'a>])> {
+        match *self {
+            Attr::Meta{ meta: Meta::Sub{ ref name, ref subs }, .. }
+                if name.is_ident("cfg_attr") => subs.split_first(),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, T> ItemWrap<'a, T> {
+    /// Return the paths inside all `#[derive(..)]`, like `Clone` and
+    /// `serde::Serialize`.
+    pub fn derives(&self) -> Vec<Path<'a>> {
+        let mut v = vec![];
+        for attr in &self.attrs {
+            if let Attr::Meta{ meta: Meta::Sub{ ref name, ref subs }, .. } =
+                *attr {
+                if name.is_ident("derive") {
+                    v.extend(subs.iter().filter_map(|sub| match *sub {
+                        Meta::Flag(ref path) => Some(path.clone()),
+                        _ => None,
+                    }));
+                }
+            }
+        }
+        v
+    }
+}
+
+impl<'a> PluginInvoke<'a> {
+    /// Return the raw source between the delimiters, including whitespaces
+    /// and comments. eg. ` a /* b */ ` in `m!{ a /* b */ }`.
+    pub fn body_source(&self) -> &'a str {
+        let loc = self.tt.1;
+        &loc[1..loc.len() - 1] // delimiters are always one byte
+    }
+}
+
+impl<'a> Item<'a> {
+    /// If it is a module with `#[path = ".."]`, return the path of the file
+    /// to load.
+    pub fn mod_path_override(&self) -> Option<&str> {
+        match self.detail {
+            ItemKind::ExternMod{ .. } | ItemKind::Mod{ .. } => (),
+            _ => return None,
+        }
+        self.attrs.iter().rev().find_map(|attr| match *attr {
+            Attr::Meta{ meta: Meta::KeyValue{
+                ref key,
+                value: Literal::StrLike{ is_bytestr: false, ref s },
+            }, .. } if key.is_ident("path") => Some(&s[..]),
+            _ => None,
+        })
+    }
+}
+
 impl<'a> Expr<'a> {
     pub fn is_item_like(&self) -> bool {
         match *self {
             Expr::Block{ .. } |
+            Expr::Unsafe(_) |
+            Expr::ConstBlock(_) |
+            Expr::TryBlock(_) |
             Expr::Loop{ .. } |
             Expr::While{ .. } |
             Expr::WhileLet{ .. } |
@@ -1,4 +1,7 @@
+use std::error::Error;
+use std::fmt;
 use super::ast::LocStr;
+use super::Span;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct LexicalError<'a> {
@@ -12,12 +15,62 @@ pub enum LexicalErrorKind {
     UnclosedComment,
     UnterminatedString,
     InvalidNumberSuffix,
+    MissingDigits,
+    IntegerOverflow,
     InvalidEscape,
+    NonAsciiByte,
+    InvalidRawIdent,
 }
 
-/// The only error may be thrown by `parse::grammar::TTParser::next()`.
+impl fmt::Display for LexicalErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::LexicalErrorKind::*;
+        f.write_str(match *self {
+            UnknowToken         => "Unknow token",
+            UnclosedComment     => "Unclosed block comment",
+            UnterminatedString  => "Unterminated string",
+            InvalidNumberSuffix => "Invalid number suffix",
+            MissingDigits       => "Missing digits",
+            IntegerOverflow     => "Integer literal is too large",
+            InvalidEscape       => "Invalid escape",
+            NonAsciiByte        => "Non-ASCII character in byte literal",
+            InvalidRawIdent     => "Invalid raw identifier",
+        })
+    }
+}
+
+/// The only error may be thrown by `parse::parser::parse_tts()`. Delimiters
+/// are single chars, so their kinds are known from the locations.
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct UnmatchedDelimError<'a>(pub LocStr<'a>);
+pub enum UnmatchedDelimError<'a> {
+    /// An open delimiter never closed, like `(` in `(a`.
+    Unclosed(LocStr<'a>),
+    /// A close delimiter without an open one, like `}` in `a }`.
+    Unopened(LocStr<'a>),
+    /// A close delimiter of a different kind from the open one, like `]` in
+    /// `(a]`.
+    Mismatched{ open: LocStr<'a>, close: LocStr<'a> },
+}
+
+impl<'a> UnmatchedDelimError<'a> {
+    /// Get the delimiter to report, which is the close one if any.
+    pub fn loc(&self) -> LocStr<'a> {
+        match *self {
+            UnmatchedDelimError::Unclosed(loc) |
+            UnmatchedDelimError::Unopened(loc) |
+            UnmatchedDelimError::Mismatched{ close: loc, .. } => loc,
+        }
+    }
+}
+
+/// Get the close delimiter of the open one `open`.
+fn close_delim(open: &str) -> &'static str {
+    match open {
+        "(" => ")",
+        "[" => "]",
+        _   => "}",
+    }
+}
 
 /// A serious syntax error which can't be caused by incomplete code and need
 /// fix immediately.
@@ -26,3 +79,153 @@ pub struct HardSyntaxError<'a> {
     pub loc:    &'a str,
     pub reason: &'static str,
 }
+
+/// An error with its position in the source, which owns everything and can be
+/// reported to users.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    pub span:     Span,
+    pub message:  String,
+    /// What is expected at `span`, if known.
+    pub expected: Option<String>,
+    /// The source text at `span`, or `None` if the span is empty.
+    pub found:    Option<String>,
+}
+
+impl ParseError {
+    /// Create an error at `loc`, which must be a slice of `source`.
+    pub fn new(source: &str, loc: &str, message: String) -> Self {
+        ParseError{
+            span:     Span::of(source, loc),
+            message,
+            expected: None,
+            found:    if loc.is_empty() { None } else { Some(loc.to_owned()) },
+        }
+    }
+
+    pub fn from_lexical(source: &str, e: &LexicalError) -> Self {
+        ParseError::new(source, e.loc, e.kind.to_string())
+    }
+
+    /// Convert an unmatched delimiter error. For a mismatched one, the close
+    /// delimiter of the open one becomes `expected`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::error::{ParseError, UnmatchedDelimError};
+    /// let s = "f(a]";
+    /// let e = UnmatchedDelimError::Mismatched{ open: &s[1..2], close: &s[3..4] };
+    /// let e = ParseError::from_unmatched(s, &e);
+    /// assert_eq!(e.to_string(), "Expect `)` to close `(` at 1:2, found `]`");
+    /// assert_eq!(e.expected, Some(String::from("`)`")));
+    /// ```
+    pub fn from_unmatched(source: &str, e: &UnmatchedDelimError) -> Self {
+        match *e {
+            UnmatchedDelimError::Unclosed(loc) => ParseError::new(
+                source, loc, String::from("Unclosed delimiter"),
+            ),
+            UnmatchedDelimError::Unopened(loc) => ParseError::new(
+                source, loc, String::from("Unexpected close delimiter"),
+            ),
+            UnmatchedDelimError::Mismatched{ open, close } => {
+                let expected = format!("`{}`", close_delim(open));
+                let (line, col) = ParseError::new(source, open, String::new())
+                                      .line_col(source);
+                let mut ret = ParseError::new(source, close, format!(
+                    "Expect {} to close `{}` at {}:{}",
+                    expected, open, line, col,
+                ));
+                ret.expected = Some(expected);
+                ret
+            },
+        }
+    }
+
+    /// Convert a syntax error. The part after `Expect ` in the reason becomes
+    /// `expected`.
+    pub fn from_syntax(source: &str, e: &HardSyntaxError) -> Self {
+        let mut ret = ParseError::new(source, e.loc, e.reason.to_owned());
+        if e.reason.starts_with("Expect ") {
+            ret.expected = Some(e.reason["Expect ".len()..].to_owned());
+        }
+        ret
+    }
+
+    /// Get the 1-based line and column (in chars) of the start of the span.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::Span;
+    /// use mair::parse::error::ParseError;
+    /// let s = "a\n名字 b";
+    /// let e = ParseError::new(s, &s[9..10], String::from("Oops"));
+    /// assert_eq!(e.span, Span{ start: 9, end: 10 });
+    /// assert_eq!(e.line_col(s), (2, 4));
+    /// ```
+    pub fn line_col(&self, source: &str) -> (usize, usize) {
+        let before = &source[..self.span.start];
+        let line_begin = before.rfind('\n').map_or(0, |i| i + 1);
+        let line = before.matches('\n').count() + 1;
+        (line, before[line_begin..].chars().count() + 1)
+    }
+
+    /// Get a displayable snippet of `source` with the span underlined.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::error::ParseError;
+    /// let s = "fn f() {\n    a b\n}";
+    /// let e = ParseError::new(s, &s[15..16], String::from("Expect `;`"));
+    /// assert_eq!(e.snippet(s).to_string(), "\
+    /// 2:7: Expect `;`, found `b`
+    ///     a b
+    ///       ^
+    /// ");
+    /// ```
+    pub fn snippet<'a>(&'a self, source: &'a str) -> Snippet<'a> {
+        Snippet{ err: self, source }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)?;
+        if let Some(ref found) = self.found {
+            write!(f, ", found `{}`", found)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParseError {
+    fn description(&self) -> &str {
+        &self.message
+    }
+}
+
+/// A `ParseError` with the source line it points to. See
+/// `ParseError::snippet()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Snippet<'a> {
+    err:    &'a ParseError,
+    source: &'a str,
+}
+
+impl<'a> fmt::Display for Snippet<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Span{ start, end } = self.err.span;
+        let (line, col) = self.err.line_col(self.source);
+        let line_begin = self.source[..start].rfind('\n')
+                             .map_or(0, |i| i + 1);
+        let line_end = self.source[start..].find('\n')
+                           .map_or(self.source.len(), |i| start + i);
+        let text = self.source[line_begin..line_end].trim_end_matches('\r');
+        let width = self.source[start..end.min(line_end)].chars().count();
+        writeln!(f, "{}:{}: {}", line, col, self.err)?;
+        writeln!(f, "{}", text)?;
+        writeln!(f, "{}{}", " ".repeat(col - 1), "^".repeat(width.max(1)))
+    }
+}
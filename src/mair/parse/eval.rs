@@ -0,0 +1,81 @@
+//! Evaluation of trivial constant expressions, like array lengths and enum
+//! discriminants.
+//!
+//! # Example
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use mair::parse::parser::parse_source;
+//! use mair::parse::ast::{ItemKind, Ty};
+//! use mair::parse::eval::eval_const_int;
+//!
+//! let (m, _) = parse_source("type T = [u8; N * (4 + 1)];").unwrap();
+//! let size = match m.items[0].detail {
+//!     ItemKind::Type{ ref origin, .. } => match **origin {
+//!         Ty::Array{ ref size, .. } => size.clone(),
+//!         _ => unreachable!(),
+//!     },
+//!     _ => unreachable!(),
+//! };
+//! let mut env = HashMap::new();
+//! assert_eq!(eval_const_int(&size, &env), None);
+//! env.insert("N", 3);
+//! assert_eq!(eval_const_int(&size, &env), Some(15));
+//! ```
+use std::collections::HashMap;
+use super::ast::{Expr, Literal, UnaryOp, BinaryOp, imax, umax};
+
+/// Evaluate an integer expression made of literals, names in `env`, parens,
+/// and arithmetic or bit operators. Return None if anything else is inside,
+/// or a literal or result overflows `imax`, or it divides by zero. Type
+/// suffixes are ignored.
+pub fn eval_const_int(expr: &Expr, env: &HashMap<&str, imax>) -> Option<imax> {
+    match *expr {
+        Expr::Literal(Literal::IntLike{ val, .. }) if val <= imax::MAX as umax =>
+            Some(val as imax),
+        Expr::Path(ref path) => match path.as_ident() {
+            Some(Ok(name)) => env.get(name).cloned(),
+            _ => None,
+        },
+        Expr::Paren(ref e) => eval_const_int(e, env),
+        Expr::Block{ ref attrs, ref stmts, ret: Some(ref e) }
+            if attrs.is_empty() && stmts.is_empty() => eval_const_int(e, env),
+        // The literal `9223372036854775808` alone is out of range.
+        Expr::UnaryOp{ op: UnaryOp::Neg, ref expr, .. } if matches!(
+            **expr,
+            Expr::Literal(Literal::IntLike{ val, .. })
+                if val == imax::MIN.unsigned_abs() as umax
+        ) => Some(imax::MIN),
+        Expr::UnaryOp{ op, ref expr, .. } => {
+            let x = eval_const_int(expr, env)?;
+            match op {
+                UnaryOp::Neg => x.checked_neg(),
+                UnaryOp::Not => Some(!x),
+                _ => None,
+            }
+        },
+        Expr::BinaryOp{ op, ref l, ref r, .. } => {
+            let a = eval_const_int(l, env)?;
+            let b = eval_const_int(r, env)?;
+            match op {
+                BinaryOp::Add => a.checked_add(b),
+                BinaryOp::Sub => a.checked_sub(b),
+                BinaryOp::Mul => a.checked_mul(b),
+                BinaryOp::Div => a.checked_div(b),
+                BinaryOp::Mod => a.checked_rem(b),
+                BinaryOp::And => Some(a & b),
+                BinaryOp::Or  => Some(a | b),
+                BinaryOp::Xor => Some(a ^ b),
+                // Fail if any bit, including the sign, is shifted out.
+                BinaryOp::Shl if (0..imax::BITS as imax).contains(&b) => {
+                    let x = a << b;
+                    if x >> b == a { Some(x) } else { None }
+                },
+                BinaryOp::Shr if (0..imax::BITS as imax).contains(&b) =>
+                    Some(a >> b),
+                _ => None,
+            }
+        },
+        _ => None,
+    }
+}
@@ -1,13 +1,17 @@
-use std::rc::Rc;
+use std::sync::Arc;
 use std::collections::HashMap;
 use std::char::from_u32;
+use std::iter::FusedIterator;
 use regex::{Regex, Captures, escape};
-use super::ast::{Literal as Lit, imax, fmax, LocStr, Ty, Delimiter};
+use super::ast::{Literal as Lit, umax, fmax, LocStr, Ty, Delimiter};
 use super::error::{LexicalError, LexicalErrorKind};
+use super::Span;
 
 pub type Token<'a> = (TokenKind<'a>, LocStr<'a>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature="serde", serde(bound(deserialize = "'de: 'input")))]
 pub enum TokenKind<'input> {
     /// An inner document containing the content.
     InnerDoc(&'input str),
@@ -23,26 +27,78 @@ pub enum TokenKind<'input> {
     Literal(Lit<'input>),
     /// A delimiter.
     Delimiter{ is_open: bool, delim: Delimiter },
-    /// A symbol.
+    /// A symbol. The longest match is taken, so `>>` in `Vec<Vec<T>>` is a
+    /// single symbol here and is split by the parser.
     Symbol(SymbolType),
+    /// A non-document comment excluding `//` or `/* */`. Only produced by
+    /// `Lexer::with_comments()` and `Lexer::with_whitespaces()`.
+    Comment{ is_block: bool, text: &'input str },
+    /// Whitespaces, or the BOM and the shebang line skipped at the beginning.
+    /// Only produced by `Lexer::with_whitespaces()`.
+    Whitespace,
 }
 
-/// An iterator over escaped `&str` producing unescaped chars
-struct EscapedChars<'a>(&'a str);
+/// An iterator over escaped `&str` producing unescaped chars, with whether it
+/// is in a byte literal.
+struct EscapedChars<'a>(&'a str, bool);
 
-/// An iterator over `str` producing `Some(TokenKind)` for token or `None` for comment.
+/// An iterator over `str` producing `Token` including comments, and
+/// whitespaces if `whitespaces` is set.
 struct Tokenizer<'input> {
-    rest: &'input str,
+    rest:        &'input str,
+    /// The BOM and the shebang line, which are not produced yet.
+    skipped:     &'input str,
+    whitespaces: bool,
 }
 
-/// An iterator over `str` producing `TokenKind`.
+/// An iterator over `str` producing `TokenKind` on demand. It ends after the
+/// input is exhausted or the first `LexicalError` is produced.
+///
+/// # Example
+///
+/// ```
+/// use mair::parse::lexer::{Lexer, TokenKind};
+/// let mut lex = Lexer::new("f ` g");
+/// assert_eq!(lex.next().unwrap().unwrap(), (TokenKind::Ident("f"), "f"));
+/// assert!(lex.next().unwrap().is_err()); // the bad token `
+/// assert!(lex.next().is_none()); // `g` is never lexed
+/// ```
 pub struct Lexer<'input> {
-    tokenizer: Tokenizer<'input>,
+    tokenizer:       Tokenizer<'input>,
+    retain_comments: bool,
+    edition:         Edition,
+}
+
+/// The Rust edition, which decides whether some words are keywords.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash, Default)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub enum Edition {
+    #[default]
+    Rust2015,
+    Rust2018,
+}
+
+/// An iterator over `str` producing `Token` with its byte span in the source.
+///
+/// # Example
+///
+/// ```
+/// use mair::parse::Span;
+/// use mair::parse::lexer::{SpannedLexer, TokenKind};
+/// let v: Vec<_> = SpannedLexer::new(r#""é" + b"#).map(|r| r.unwrap()).collect();
+/// assert_eq!(v.len(), 3);
+/// assert_eq!(v[0].1, Span{ start: 0, end: 4 }); // `é` takes 2 bytes
+/// assert_eq!(v[2], ((TokenKind::Ident("b"), "b"), Span{ start: 7, end: 8 }));
+/// ```
+pub struct SpannedLexer<'input> {
+    source: &'input str,
+    lexer:  Lexer<'input>,
 }
 
 macro_rules! define_symbols(
     ($($tok:ident = $s:tt;)+) => {
         #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        #[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
         pub enum SymbolType {
             $($tok,)+
         }
@@ -69,6 +125,7 @@ macro_rules! define_symbols(
 macro_rules! define_keywords {
     ($($kw:ident = $s:tt;)+) => {
         #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+        #[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
         pub enum KeywordType {
             $($kw,)+
         }
@@ -101,6 +158,7 @@ define_symbols!{
     Dot         = ".";
     DotDot      = "..";
     DotDotDot   = "...";
+    DotDotEq    = "..=";
     Colon       = ":";
     ColonColon  = "::";
     Bang        = "!";
@@ -193,8 +251,25 @@ define_keywords! {
     Where     = "where";
     While     = "while";
     Yield     = "yield";
+    // Since Rust 2018
+    Async     = "async";
+    Await     = "await";
+    Dyn       = "dyn";
+    Try       = "try";
 } // define_keywords!
 
+impl KeywordType {
+    /// Return the first edition where it is a keyword. It is lexed as an
+    /// identifier in editions before.
+    pub fn since(self) -> Edition {
+        use self::KeywordType::*;
+        match self {
+            Async | Await | Dyn | Try => Edition::Rust2018,
+            _ => Edition::Rust2015,
+        }
+    }
+}
+
 /// The regex match a char(maybe escaped).
 const RESTR_CHAR: &str = r#"(?x:
     (?P<char_normal>[[:^cntrl:]&&[^\\]])|
@@ -207,9 +282,9 @@ const RESTR_CHAR: &str = r#"(?x:
 
 const RESTR_NUM: &str = r#"(?x:
     (?:
-        0b(?P<num_bin>[01_]+)|
-        0o(?P<num_oct>[0-7_]+)|
-        0x(?P<num_hex>[[:xdigit:]]+)|
+        0b(?P<num_bin>[01_]*)|
+        0o(?P<num_oct>[0-7_]*)|
+        0x(?P<num_hex>[[:xdigit:]_]*)|
         (?P<num_body>
             \d[\d_]*
             (?P<num_float_like>
@@ -230,6 +305,7 @@ lazy_static! {
         (?P<block_outerdoc_beg_eat1>/\*\*[^*/])|
         (?P<block_comment_beg>/\*)|
         (?P<num>{num})|
+        (?P<raw_ident>r\#(?P<raw_ident_name>[A-Za-z_]\w*))|
         (?P<raw_string_beg>(?P<raw_string_byte>b)?r(?P<raw_string_hashes>\#*)")|
         (?P<string>
             (?P<string_byte>b)?"
@@ -251,15 +327,15 @@ lazy_static! {
 
     static ref RE_NUM_SUFFIX: Regex = Regex::new(
         r"(?x)\A(?:
-            (?P<int_like>[iu](?:8|16|32|64|size))|
+            (?P<int_like>[iu](?:8|16|32|64|128|size))|
             f(?:32|64)
         )?\z"
     ).unwrap();
 }
 
 impl<'a> EscapedChars<'a> {
-    fn new(s: &'a str) -> Self {
-        EscapedChars(s)
+    fn new(s: &'a str, is_byte: bool) -> Self {
+        EscapedChars(s, is_byte)
     }
 }
 
@@ -268,9 +344,9 @@ impl<'a> Iterator for EscapedChars<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         lazy_static! {
-            static ref RE_ESCAPED: Regex = Regex::new(
-                &format!(r"\A{}", RESTR_CHAR)
-            ).unwrap();
+            static ref RE_ESCAPED: Regex = Regex::new(&format!(
+                r"\A(?:{}|(?P<char_raw>\r\n|[^\\\r]))", RESTR_CHAR,
+            )).unwrap();
         }
 
         let err = Some(Err(self.0));
@@ -282,6 +358,11 @@ impl<'a> Iterator for EscapedChars<'a> {
                 self.0 = &self.0[cap[0].len()..];
                 Some(Ok(if let Some(s) = cap.name("char_normal") {
                     s.as_str().chars().next().unwrap()
+                } else if let Some(s) = cap.name("char_raw") { // only in strings
+                    match s.as_str() {
+                        "\r\n" => '\n',
+                        s => s.chars().next().unwrap(),
+                    }
                 } else if let Some(s) = cap.name("char_escape_simple") {
                     match s.as_str().as_bytes()[0] {
                         b'n'  => '\n',
@@ -296,8 +377,14 @@ impl<'a> Iterator for EscapedChars<'a> {
                         _     => return err,
                     }
                 } else if let Some(s) = cap.name("char_escape_ascii") {
-                    u8::from_str_radix(s.as_str(), 16).unwrap() as char // checked by regex
+                    match u8::from_str_radix(s.as_str(), 16).unwrap() { // checked by regex
+                        c if c < 0x80 || self.1 => c as char,
+                        _ => return err, // only ASCII out of byte literals
+                    }
                 } else if let Some(s) = cap.name("char_escape_unicode") {
+                    if self.1 { // not allowed in byte literals
+                        return err;
+                    }
                     match from_u32(u32::from_str_radix(s.as_str(), 16).unwrap()) { // ..
                         Some(c) => c,
                         None    => return err,
@@ -313,8 +400,15 @@ impl<'a> Iterator for EscapedChars<'a> {
 }
 
 impl<'input> Tokenizer<'input> {
-    pub fn new(input: &'input str) -> Self {
-        Tokenizer{ rest: input }
+    pub fn new(input: &'input str, whitespaces: bool) -> Self {
+        let rest = if input.starts_with('\u{FEFF}') {
+            &input['\u{FEFF}'.len_utf8()..] // skip the BOM
+        } else {
+            input
+        };
+        let rest = skip_shebang(rest);
+        let skipped = &input[..input.len() - rest.len()];
+        Tokenizer{ rest, skipped, whitespaces }
     }
 
     fn advance(&mut self, len: usize) {
@@ -322,6 +416,12 @@ impl<'input> Tokenizer<'input> {
         self.rest = &self.rest[len..];
     }
 
+    /// Skip all the rest input, so no more tokens or errors are produced.
+    fn stop(&mut self) {
+        let len = self.rest.len();
+        self.advance(len);
+    }
+
     /// Consume block comment inner(without the starting tag) till the ending tag.
     /// Return the comment content.
     fn eat_block_comment(&mut self) -> Result<&'input str, LexicalErrorKind> {
@@ -342,6 +442,25 @@ impl<'input> Tokenizer<'input> {
         Ok(&sbegin[..sbegin.len() - self.rest.len() - 2]) // excluding `*/`
     }
 
+    /// Consume the `.` after a decimal integer without suffix like `1.`, and
+    /// return whether it is eaten. The `.` is not eaten if followed by `.`, `_`
+    /// or a letter, like `1..2` or `1.foo()`.
+    fn eat_trailing_dot(&mut self, cap: &Captures) -> bool {
+        let is_plain_int = cap.name("num_body").is_some() &&
+            cap["num_float_like"].is_empty() &&
+            cap["num_suffix"].is_empty();
+        let mut it = self.rest.chars();
+        match (it.next(), it.next()) {
+            (Some('.'), Some(c)) if c == '.' || c == '_' || c.is_alphabetic() =>
+                false,
+            (Some('.'), _) if is_plain_int => {
+                self.advance(1);
+                true
+            },
+            _ => false,
+        }
+    }
+
     /// Consume raw string inner(without the starting tag) till the ending tag.
     /// Return the content of the string.
     fn eat_raw_string(&mut self, hashes: usize) -> Result<&'input str, LexicalErrorKind> {
@@ -356,12 +475,29 @@ impl<'input> Tokenizer<'input> {
     }
 }
 
+/// Skip the shebang line like `#!/usr/bin/env run-cargo-script` at the
+/// beginning of input, but not an inner attribute like `#![no_std]`.
+fn skip_shebang(input: &str) -> &str {
+    if input.starts_with("#!") && !input[2..].trim_start().starts_with('[') {
+        match input.find('\n') {
+            Some(p) => &input[p..],
+            None    => &input[input.len()..],
+        }
+    } else {
+        input
+    }
+}
+
 /// Parse a char-like literal captured.
 fn parse_cap_char<'a>(cap: &Captures<'a>) -> Result<Lit<'a>, LexicalErrorKind> {
     let s = &cap["char_content"];
-    match EscapedChars::new(s).next().unwrap() { // must have at least 1 char
+    let is_byte = cap.name("char_byte").is_some();
+    if is_byte && !s.is_ascii() {
+        Err(LexicalErrorKind::NonAsciiByte)?
+    }
+    match EscapedChars::new(s, is_byte).next().unwrap() { // must have at least 1 char
         Ok(ch) if s.as_bytes()[0] != b'\'' => Ok(Lit::CharLike{ // `'''` is invalid
-            is_byte: cap.name("char_byte").is_some(),
+            is_byte,
             ch,
         }),
         _ => Err(LexicalErrorKind::InvalidEscape), // TODO: save the position
@@ -379,10 +515,20 @@ fn parse_cap_num<'a>(cap: &Captures<'a>) -> Result<Lit<'a>, LexicalErrorKind> {
                 else if let Some(s) = cap.name("num_body") { (10, s) }
                 else { unreachable!() };
     let s = s.as_str().replace("_", "");
+    if s.is_empty() { // `0x`, `0b_`
+        return Err(LexicalErrorKind::MissingDigits);
+    }
     let mut lit = if cap.name("num_float_like").map_or(false, |s| !s.as_str().is_empty()) {
         FloatLike{ ty: None, val: s.parse().unwrap() } // checked by regex
     } else {
-        IntLike{ ty: None, val: imax::from_str_radix(&s, radix).unwrap() } // ..
+        match umax::from_str_radix(&s, radix) {
+            Ok(val) => IntLike{ ty: None, val },
+            // `1000000000000000000000000000000000000000f64` is still a valid
+            // float.
+            Err(_) if radix == 10 && cap["num_suffix"].starts_with('f') =>
+                FloatLike{ ty: None, val: s.parse().unwrap() },
+            Err(_) => return Err(LexicalErrorKind::IntegerOverflow),
+        }
     };
     if let Some(cap_suf) = RE_NUM_SUFFIX.captures(cap.name("num_suffix").unwrap().as_str()) {
         if !cap_suf[0].is_empty() {
@@ -415,30 +561,44 @@ fn parse_cap_num<'a>(cap: &Captures<'a>) -> Result<Lit<'a>, LexicalErrorKind> {
 /// Parse a string-like literal.
 fn parse_str_string(source: &str, is_bytestr: bool, is_raw: bool)
         -> Result<Lit, LexicalErrorKind> {
+    if is_bytestr && !source.is_ascii() {
+        Err(LexicalErrorKind::NonAsciiByte)?
+    }
     let mut s;
     if is_raw {
         s = String::from(source)
     } else {
         s = String::new();
-        for ret in EscapedChars::new(source) {
+        for ret in EscapedChars::new(source, is_bytestr) {
             match ret {
                 Ok(c)  => s.push(c),
                 Err(_) => Err(LexicalErrorKind::InvalidEscape)?, // TODO: save the position
             }
         }
     };
-    Ok(Lit::StrLike{ is_bytestr, s: Rc::new(s) })
+    Ok(Lit::StrLike{ is_bytestr, s: Arc::new(s) })
 }
 
 impl<'input> Iterator for Tokenizer<'input> {
-    type Item =
-        Result<Option<(TokenKind<'input>, &'input str)>, LexicalError<'input>>;
+    type Item = Result<Token<'input>, LexicalError<'input>>;
 
     fn next(&mut self) -> Option<Self::Item> {
         use self::TokenKind::*;
         use self::LexicalErrorKind::*;
 
         let slast = self.rest.trim_left();
+        if self.whitespaces {
+            if !self.skipped.is_empty() {
+                let loc = self.skipped;
+                self.skipped = &loc[loc.len()..];
+                return Some(Ok((Whitespace, loc)));
+            }
+            if slast.len() < self.rest.len() {
+                let loc = &self.rest[..self.rest.len() - slast.len()];
+                self.rest = slast;
+                return Some(Ok((Whitespace, loc)));
+            }
+        }
         self.rest = slast;
         if self.rest.is_empty() {
             None
@@ -448,41 +608,54 @@ impl<'input> Iterator for Tokenizer<'input> {
             let mut f = || -> Result<_, LexicalErrorKind> {
                 // wrap for the carriers inside
                 Ok(match cap.get(0).unwrap().as_str() {
-                    m if is("line_innerdoc")        => Some(InnerDoc(&m[3..])),
-                    m if is("line_outerdoc")        => Some(OuterDoc(&m[3..])),
-                    _ if is("line_comment")         => None,
-                    m if is("lifetime")             => Some(Lifetime(&m[1..])),
+                    m if is("line_innerdoc")        => InnerDoc(&m[3..]),
+                    m if is("line_outerdoc")        => OuterDoc(&m[3..]),
+                    m if is("line_comment")         =>
+                        Comment{ is_block: false, text: &m[2..] },
+                    m if is("lifetime")             => Lifetime(&m[1..]),
                     m if is("keyword")              => if m == "true" {
-                        Some(Literal(Lit::Bool(true)))
+                        Literal(Lit::Bool(true))
                     } else if m == "false" {
-                        Some(Literal(Lit::Bool(false)))
+                        Literal(Lit::Bool(false))
+                    } else {
+                        Keyword(KEYWORDS[m])
+                    },
+                    m if is("ident")                => Ident(m),
+                    _ if is("raw_ident")            => {
+                        let name = cap.name("raw_ident_name").unwrap().as_str();
+                        if name == "_" {
+                            Err(InvalidRawIdent)?
+                        }
+                        Ident(name)
+                    },
+                    _ if is("block_innerdoc_beg")   => InnerDoc(self.eat_block_comment()?),
+                    _ if is("char")                 => Literal(parse_cap_char(&cap)?),
+                    _ if is("num")                  => if self.eat_trailing_dot(&cap) {
+                        let s = cap["num_body"].replace("_", "");
+                        Literal(Lit::FloatLike{ ty: None, val: s.parse().unwrap() })
                     } else {
-                        Some(Keyword(KEYWORDS[m]))
+                        Literal(parse_cap_num(&cap)?)
                     },
-                    m if is("ident")                => Some(Ident(m)),
-                    _ if is("block_innerdoc_beg")   => Some(InnerDoc(self.eat_block_comment()?)),
-                    _ if is("char")                 => Some(Literal(parse_cap_char(&cap)?)),
-                    _ if is("num")                  => Some(Literal(parse_cap_num(&cap)?)),
-                    m if is("symbol")               => Some(Symbol(SYMBOLS[m])),
+                    m if is("symbol")               => Symbol(SYMBOLS[m]),
                     _ if is("string")               => {
                         if !is("string_closed") {
                             Err(UnterminatedString)?
                         } else {
                             let content = cap.name("string_content").unwrap().as_str();
-                            Some(Literal(parse_str_string(content, is("string_byte"), false)?))
+                            Literal(parse_str_string(content, is("string_byte"), false)?)
                         }
                     },
                     _ if is("raw_string_beg")       => {
                         let s = self.eat_raw_string(cap["raw_string_hashes"].len())?;
-                        Some(Literal(parse_str_string(s, is("raw_string_byte"), true)?))
+                        Literal(parse_str_string(s, is("raw_string_byte"), true)?)
                     },
                     _ if is("block_outerdoc_beg_eat1")  => {
                         self.rest = &slast[cap[0].len() - 1..]; // put the eaten first char back
-                        Some(OuterDoc(self.eat_block_comment()?))
+                        OuterDoc(self.eat_block_comment()?)
                     },
                     _ if is("block_comment_beg")    => {
-                        self.eat_block_comment()?;
-                        None
+                        let text = self.eat_block_comment()?;
+                        Comment{ is_block: true, text }
                     },
                     m if is("delimiter")            => {
                         use self::Delimiter::*;
@@ -495,25 +668,82 @@ impl<'input> Iterator for Tokenizer<'input> {
                             "[" | "]" => Bracket,
                             _         => Brace,
                         };
-                        Some(Delimiter{ is_open, delim })
+                        Delimiter{ is_open, delim }
                     },
                     _ => unreachable!(),
                 })
             };
-            match f() {
-                Ok(None)        => Some(Ok(None)),
-                Ok(Some(tokty)) => Some(Ok(Some((tokty, &slast[..slast.len() - self.rest.len()])))),
-                Err(e)          => Some(Err(LexicalError{ loc: slast, kind: e })),
-            }
+            let err = match f() {
+                Ok(tokty) => return Some(Ok((tokty, &slast[..slast.len() - self.rest.len()]))),
+                Err(UnterminatedString) if is("raw_string_beg") => LexicalError{
+                    loc: &slast[..cap[0].len()], // the opening `r#"`
+                    kind: UnterminatedString,
+                },
+                Err(e)    => LexicalError{ loc: slast, kind: e },
+            };
+            self.stop();
+            Some(Err(err))
         } else { // regex match fails
-            Some(Err(LexicalError{ loc: self.rest, kind: UnknowToken}))
+            let loc = self.rest;
+            self.stop();
+            Some(Err(LexicalError{ loc, kind: UnknowToken}))
         }
     }
 }
 
+impl<'input> FusedIterator for Tokenizer<'input> {}
+
 impl<'input> Lexer<'input> {
+    /// Create a lexer for Rust 2015.
     pub fn new(input: &'input str) -> Self {
-        Lexer{ tokenizer: Tokenizer::new(input) }
+        Lexer{
+            tokenizer: Tokenizer::new(input, false),
+            retain_comments: false,
+            edition: Edition::default(),
+        }
+    }
+
+    /// Create a lexer which also produces `TokenKind::Comment`.
+    pub fn with_comments(input: &'input str) -> Self {
+        Lexer{ retain_comments: true, ..Lexer::new(input) }
+    }
+
+    /// Create a lexer which also produces `TokenKind::Comment` and
+    /// `TokenKind::Whitespace`, useful for highlighting. The tokens produced
+    /// cover the whole input without gaps, unless an error occurs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::lexer::{Lexer, TokenKind};
+    /// let s = "\u{FEFF}#!/bin/run\nfn f() { /* c */ }\n";
+    /// let v: Vec<_> = Lexer::with_whitespaces(s).map(Result::unwrap).collect();
+    /// assert_eq!(v[0], (TokenKind::Whitespace, "\u{FEFF}#!/bin/run"));
+    /// assert_eq!(v[1], (TokenKind::Whitespace, "\n"));
+    /// assert_eq!(v.iter().map(|t| t.1).collect::<String>(), s);
+    /// ```
+    pub fn with_whitespaces(input: &'input str) -> Self {
+        Lexer{
+            tokenizer: Tokenizer::new(input, true),
+            ..Lexer::with_comments(input)
+        }
+    }
+
+    /// Set the edition, which decides whether words like `async` are
+    /// keywords.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::lexer::{Lexer, Edition, TokenKind, KeywordType};
+    /// let s = "let async = 1;";
+    /// let t15 = Lexer::new(s).nth(1).unwrap().unwrap();
+    /// let t18 = Lexer::new(s).edition(Edition::Rust2018).nth(1).unwrap().unwrap();
+    /// assert_eq!(t15, (TokenKind::Ident("async"), "async"));
+    /// assert_eq!(t18, (TokenKind::Keyword(KeywordType::Async), "async"));
+    /// ```
+    pub fn edition(self, edition: Edition) -> Self {
+        Lexer{ edition, ..self }
     }
 }
 
@@ -523,12 +753,41 @@ impl<'input> Iterator for Lexer<'input> {
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             return match self.tokenizer.next() {
-                None                                 => None,
-                Some(Ok(None))                       => continue, // skip comment as space
-                Some(Ok(Some((tokty, s))))           => Some(Ok((tokty, s))),
-                Some(Err(LexicalError{ loc, kind })) =>
-                    Some(Err(LexicalError{ loc, kind })),
+                Some(Ok((TokenKind::Comment{ .. }, _)))
+                    if !self.retain_comments => continue, // skip as space
+                Some(Ok((TokenKind::Keyword(kw), loc)))
+                    if kw.since() > self.edition =>
+                    Some(Ok((TokenKind::Ident(loc), loc))),
+                r => r,
             }
         }
     }
 }
+
+impl<'input> FusedIterator for Lexer<'input> {}
+
+impl<'input> SpannedLexer<'input> {
+    pub fn new(input: &'input str) -> Self {
+        SpannedLexer{ source: input, lexer: Lexer::new(input) }
+    }
+
+    /// Create a lexer over `Lexer::with_whitespaces()`, whose spans cover the
+    /// whole input without gaps, unless an error occurs.
+    pub fn with_whitespaces(input: &'input str) -> Self {
+        SpannedLexer{ source: input, lexer: Lexer::with_whitespaces(input) }
+    }
+}
+
+impl<'input> Iterator for SpannedLexer<'input> {
+    type Item = Result<(Token<'input>, Span), LexicalError<'input>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let source = self.source;
+        self.lexer.next().map(|r| r.map(|tok| {
+            let span = Span::of(source, tok.1);
+            (tok, span)
+        }))
+    }
+}
+
+impl<'input> FusedIterator for SpannedLexer<'input> {}
@@ -1,8 +1,14 @@
 #[macro_use] mod ttstream; // match_eat!()
 pub mod ast;
 pub mod error;
+pub mod eval;
 #[macro_use] pub mod lexer; // symbol_type!(), keyword_type!()
+pub mod owned;
 pub mod parser;
+#[cfg(feature="proc-macro2")] pub mod proc_macro;
+pub mod print;
+pub mod visit;
+pub mod visit_mut;
 
 /// Get item offset of b from a, similar to pointer subtraction
 /// `(const T *)a - (const T *)b` in C/C++.
@@ -33,6 +39,57 @@ pub fn str_ptr_diff(a: &str, b: &str) -> isize {
     ptr_diff(a.as_ptr(), b.as_ptr())
 }
 
+/// A byte range `start..end` in the source.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature="serde", derive(Serialize, Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end:   usize,
+}
+
+impl Span {
+    /// Get the span of `loc`, which must be a slice of `source`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::Span;
+    /// let s = "let 名字 = 1;";
+    /// assert_eq!(Span::of(s, &s[4..10]), Span{ start: 4, end: 10 });
+    /// ```
+    pub fn of(source: &str, loc: &str) -> Self {
+        let start = str_ptr_diff(loc, source) as usize;
+        Span{ start, end: start + loc.len() }
+    }
+
+    /// Get the span from the first to the last of `tts`, including the
+    /// whitespaces and comments between them. Return None if `tts` is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use mair::parse::Span;
+    /// use mair::parse::lexer::Lexer;
+    /// use mair::parse::parser::parse_tts;
+    /// let s = "a /* b */ + (c)";
+    /// let toks: Vec<_> = Lexer::new(s).map(Result::unwrap).collect();
+    /// let tts = parse_tts(s, &toks).unwrap();
+    /// let sp = Span::of_tts(s, &tts).unwrap();
+    /// assert_eq!(&s[sp.start..sp.end], s);
+    /// assert_eq!(Span::of_tts(s, &tts[2..]), Some(Span{ start: 12, end: 15 }));
+    /// assert_eq!(Span::of_tts(s, &[]), None);
+    /// ```
+    pub fn of_tts(source: &str, tts: &[ast::TT]) -> Option<Self> {
+        match (tts.first(), tts.last()) {
+            (Some(&(_, l)), Some(&(_, r))) => Some(Span{
+                start: Span::of(source, l).start,
+                end:   Span::of(source, r).end,
+            }),
+            _ => None,
+        }
+    }
+}
+
 /// Generate a map from character indices to line and column numbers,
 ///   including the position next to the end of input (EOI).
 ///
@@ -68,3 +125,81 @@ pub fn gen_position_map(s: &str) -> Vec<(usize, usize)> {
     v.push((lastline + 1, 1));
     v
 }
+
+/// The unit of columns counted by `LineIndex`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ColumnUnit {
+    /// Unicode scalar values, as `char`s in Rust.
+    Char,
+    /// UTF-16 code units, as used by editors following the Language Server
+    /// Protocol.
+    Utf16,
+}
+
+/// A map from byte offsets to 1-based line and column numbers, built once
+/// from the source. Lines end with `\n` or `\r\n`.
+///
+/// # Example
+///
+/// ```
+/// use mair::parse::{LineIndex, ColumnUnit};
+/// let s = "a\r\n名字 = '🦀';\n";
+/// let idx = LineIndex::new(s, ColumnUnit::Char);
+/// assert_eq!(idx.offset_to_pos(0), (1, 1));
+/// assert_eq!(idx.offset_to_pos(1), (1, 2)); // `\r`
+/// assert_eq!(idx.offset_to_pos(6), (2, 2)); // `字`
+/// assert_eq!(idx.offset_to_pos(17), (2, 8)); // `'` after `🦀`
+/// assert_eq!(idx.offset_to_pos(s.len()), (3, 1));
+/// let idx = LineIndex::new(s, ColumnUnit::Utf16);
+/// assert_eq!(idx.offset_to_pos(17), (2, 9));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    /// The offsets where lines begin.
+    line_begins: Vec<u32>,
+    /// The offsets of chars which take more bytes than columns, with the
+    /// total excess bytes of all chars before them.
+    wide_chars:  Vec<(u32, u32)>,
+}
+
+impl LineIndex {
+    /// Build the index of `source`, counting columns in `unit`.
+    pub fn new(source: &str, unit: ColumnUnit) -> Self {
+        let mut line_begins = vec![0];
+        let mut wide_chars = vec![];
+        let mut excess = 0;
+        for (i, c) in source.char_indices() {
+            match c {
+                '\n' => line_begins.push(i as u32 + 1),
+                _ if c.len_utf8() > 1 => {
+                    wide_chars.push((i as u32, excess));
+                    excess += match unit {
+                        ColumnUnit::Char  => c.len_utf8() - 1,
+                        ColumnUnit::Utf16 => c.len_utf8() - c.len_utf16(),
+                    } as u32;
+                },
+                _ => (),
+            }
+        }
+        // A sentinel to get the total excess.
+        wide_chars.push((source.len() as u32, excess));
+        LineIndex{ line_begins, wide_chars }
+    }
+
+    /// Get the 1-based line and column of the byte `offset`, which should be
+    /// on a char boundary and not beyond the end of the source.
+    pub fn offset_to_pos(&self, offset: usize) -> (u32, u32) {
+        let offset = offset as u32;
+        let line = self.line_begins.partition_point(|&b| b <= offset);
+        let begin = self.line_begins[line - 1];
+        let col = offset - begin - (self.excess_before(offset) -
+                                    self.excess_before(begin));
+        (line as u32, col + 1)
+    }
+
+    /// The total excess bytes of wide chars before `offset`.
+    fn excess_before(&self, offset: u32) -> u32 {
+        let i = self.wide_chars.partition_point(|&(o, _)| o < offset);
+        self.wide_chars[i.min(self.wide_chars.len() - 1)].1
+    }
+}
@@ -0,0 +1,163 @@
+//! ASTs owning their source.
+//!
+//! Every AST node borrows slices of the source, so the AST cannot live longer
+//! than the source string. `OwnedMod` keeps them together, which allows
+//! caching parse results or sending them to other threads. The source is
+//! shared, so a single item can be taken out as an `OwnedItem` by cloning only
+//! that item. Note that the item still keeps the whole source alive, which is
+//! cheaper than copying and reparsing its text, but costs memory when only a
+//! few items of a large file are cached. See `benches/owned.rs` for the cost
+//! of taking an item out compared with cloning the whole module.
+//!
+//! # Example
+//!
+//! ```
+//! use std::thread;
+//! use mair::parse::lexer::Lexer;
+//! use mair::parse::parser::{parse_tts, parse_crate};
+//! use mair::parse::owned::OwnedMod;
+//!
+//! let m = OwnedMod::new(String::from("fn f() {}"), |s| {
+//!     let toks: Vec<_> = Lexer::new(s).map(Result::unwrap).collect();
+//!     parse_crate(s, parse_tts(s, &toks).unwrap()).0
+//! });
+//! let n = thread::spawn(move || m.module().items.len()).join().unwrap();
+//! assert_eq!(n, 1);
+//! ```
+//!
+//! Keep only the second item, after the module is dropped:
+//!
+//! ```
+//! use mair::parse::lexer::Lexer;
+//! use mair::parse::parser::{parse_tts, parse_crate};
+//! use mair::parse::owned::OwnedMod;
+//! use mair::parse::ast::ItemKind;
+//!
+//! let m = OwnedMod::new(String::from("fn f() {} struct S;"), |s| {
+//!     let toks: Vec<_> = Lexer::new(s).map(Result::unwrap).collect();
+//!     parse_crate(s, parse_tts(s, &toks).unwrap()).0
+//! });
+//! let item = m.item(1).unwrap();
+//! drop(m);
+//! match item.item().detail {
+//!     ItemKind::StructUnit{ name, .. } => assert_eq!(name, Ok("S")),
+//!     _ => unreachable!(),
+//! }
+//! assert_eq!(item.source(), "fn f() {} struct S;");
+//! ```
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+use super::ast::{Mod, Item};
+use super::lexer::Token;
+
+/// A module AST together with the source it borrows.
+pub struct OwnedMod {
+    // Fields are dropped in order, so `module` is dropped before `source`.
+    module: Mod<'static>,
+    source: Arc<String>,
+}
+
+impl OwnedMod {
+    /// Build the module from `source` with `f`, which is usually a parser.
+    pub fn new<F>(source: String, f: F) -> Self
+    where F: for<'a> FnOnce(&'a str) -> Mod<'a> {
+        let module = f(&source);
+        // `module` can only borrow from `source` or `'static` data since `f`
+        // must work for any `'a`. The content of `source` is on the heap, so
+        // it keeps still when `source` is moved, and is never mutated or
+        // dropped before `module`.
+        let module = unsafe { mem::transmute::<Mod, Mod<'static>>(module) };
+        OwnedMod{ module, source: Arc::new(source) }
+    }
+
+    /// Get the source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the module, whose lifetime is shortened to `self`.
+    pub fn module<'s>(&'s self) -> &'s Mod<'s> {
+        &self.module
+    }
+
+    /// Clone the `i`-th item, which shares the source with `self`. Return
+    /// None if `i` is out of range.
+    pub fn item(&self, i: usize) -> Option<OwnedItem> {
+        self.module.items.get(i).map(|item| OwnedItem{
+            // It borrows the same heap content which `source` keeps alive.
+            item:   item.clone(),
+            source: self.source.clone(),
+        })
+    }
+}
+
+/// An item AST together with the source it borrows, usually taken out of an
+/// `OwnedMod`. The whole source of the module is kept alive as long as any
+/// item taken out of it.
+pub struct OwnedItem {
+    // Dropped before `source`, the same as `OwnedMod`.
+    item:   Item<'static>,
+    source: Arc<String>,
+}
+
+impl OwnedItem {
+    /// Get the whole source, not only the part of this item.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the item, whose lifetime is shortened to `self`.
+    pub fn item<'s>(&'s self) -> &'s Item<'s> {
+        &self.item
+    }
+}
+
+/// Tokens together with the source they borrow, like tokens lexed from a
+/// printed source which is not kept anywhere else.
+pub struct OwnedTokens {
+    // Dropped before `source`, the same as `OwnedMod`.
+    tokens: Vec<Token<'static>>,
+    source: String,
+}
+
+impl OwnedTokens {
+    /// Build the tokens from `source` with `f`, which is usually a lexer.
+    pub fn new<F>(source: String, f: F) -> Self
+    where F: for<'a> FnOnce(&'a str) -> Vec<Token<'a>> {
+        let tokens = f(&source);
+        // The same as `OwnedMod::new()`.
+        let tokens = unsafe {
+            mem::transmute::<Vec<Token>, Vec<Token<'static>>>(tokens)
+        };
+        OwnedTokens{ tokens, source }
+    }
+
+    /// Get the source.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the tokens, whose lifetime is shortened to `self`.
+    pub fn tokens<'s>(&'s self) -> &'s [Token<'s>] {
+        &self.tokens
+    }
+}
+
+impl fmt::Debug for OwnedItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.item.fmt(f)
+    }
+}
+
+impl fmt::Debug for OwnedMod {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.module.fmt(f)
+    }
+}
+
+impl fmt::Debug for OwnedTokens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.tokens.fmt(f)
+    }
+}
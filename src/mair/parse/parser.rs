@@ -1,12 +1,23 @@
-use super::lexer::{TokenKind as Tokk, SymbolType, Token};
+use std::cell::Cell;
+use super::lexer::{TokenKind as Tokk, SymbolType, Token, Lexer, Edition};
 use super::ast::*;
-use super::error::{UnmatchedDelimError, HardSyntaxError};
+use super::error::{UnmatchedDelimError, HardSyntaxError, ParseError};
 use super::ttstream::TTStream;
 use self::Delimiter::*;
 
 pub struct Parser<'t, 'e> where 't: 'e {
-    tts:  TTStream<'t>,
-    errs: &'e mut Vec<HardSyntaxError<'t>>,
+    source: &'t str,
+    tts:    TTStream<'t>,
+    errs:   &'e mut Vec<HardSyntaxError<'t>>,
+}
+
+/// Qualifiers before `fn`.
+struct FnQuals<'t> {
+    begin:     LocStr<'t>,
+    is_const:  bool,
+    is_async:  bool,
+    is_unsafe: bool,
+    abi:       ABI<'t>,
 }
 
 /// Parse tokens into `TT`s.
@@ -17,7 +28,7 @@ pub fn parse_tts<'t>(
     let (rest, tts) = parse_tts_helper(source, toks)?;
     match rest.first() {
         None            => Ok(tts),
-        Some(&(_, loc)) => Err(UnmatchedDelimError(loc)),
+        Some(&(_, loc)) => Err(UnmatchedDelimError::Unopened(loc)),
     }
 }
 
@@ -45,7 +56,7 @@ fn parse_tts_helper<'t, 'a>(
                 let (rest, tts_inner) = parse_tts_helper(source, &toks[1..])?;
                 toks = rest;
                 match toks.first() {
-                    None => return Err(UnmatchedDelimError(loc)),
+                    None => return Err(UnmatchedDelimError::Unclosed(loc)),
                     Some(&(Tokk::Delimiter{ is_open: false, delim: delim2 }, loc2)) => {
                         toks = &toks[1..];
                         if delim == delim2 {
@@ -54,13 +65,18 @@ fn parse_tts_helper<'t, 'a>(
                                 delim,
                             }, str_range(source, loc, loc2)));
                         } else {
-                            return Err(UnmatchedDelimError(loc2));
+                            return Err(UnmatchedDelimError::Mismatched{
+                                open: loc,
+                                close: loc2,
+                            });
                         }
                     },
                     Some(_) => unreachable!(), // parse_tts_helper() only stops
                                                // before close delimiter or EOF
                 }
             },
+            Some(&(Tokk::Comment{ .. }, _)) |
+            Some(&(Tokk::Whitespace, _)) => toks = &toks[1..],
             Some(&(ref tokk, loc)) => {
                 toks = &toks[1..];
                 tts.push((TTKind::Token(tokk.clone()), loc));
@@ -69,6 +85,76 @@ fn parse_tts_helper<'t, 'a>(
     }
 }
 
+/// Parse the whole `tts` with `f`. TTs left are reported as one error
+/// spanning all of them.
+fn parse_whole<'t, T, F>(
+    source: &'t str,
+    tts:    Vec<TT<'t>>,
+    f:      F,
+) -> (T, Vec<HardSyntaxError<'t>>)
+where F: for<'e> FnOnce(&mut Parser<'t, 'e>) -> T {
+    let mut errs = vec![];
+    let x = {
+        let mut p = Parser::new(source, tts, &mut errs);
+        let x = f(&mut p);
+        let first = p.tts.next().map(|(_, loc)| loc);
+        let last = p.tts.by_ref().last().map(|(_, loc)| loc);
+        if let Some(first) = first {
+            let loc = str_range(source, first, last.unwrap_or(first));
+            p.err(loc, "Expect the end of input");
+        }
+        x
+    };
+    (x, errs)
+}
+
+/// Parse a pattern maybe with alternatives like `Some(1) | None`.
+pub fn parse_pat<'t>(
+    source: &'t str,
+    tts: Vec<TT<'t>>,
+) -> (Pat<'t>, Vec<HardSyntaxError<'t>>) {
+    parse_whole(source, tts, |p| p.eat_pat_alt())
+}
+
+/// Parse a type, which may be a trait object without `dyn` like `A + B`.
+pub fn parse_ty<'t>(
+    source: &'t str,
+    tts: Vec<TT<'t>>,
+) -> (Ty<'t>, Vec<HardSyntaxError<'t>>) {
+    parse_whole(source, tts, |p| {
+        let ty = p.eat_ty(true);
+        if ty == Ty::Traits(vec![]) {
+            p.err_prev("Expect a type");
+        }
+        ty
+    })
+}
+
+/// Parse an expression, where struct expressions are allowed.
+pub fn parse_expr<'t>(
+    source: &'t str,
+    tts: Vec<TT<'t>>,
+) -> (Expr<'t>, Vec<HardSyntaxError<'t>>) {
+    parse_whole(source, tts, |p| p.eat_expr(false, true))
+}
+
+/// Parse an item with its outer attributes. Return `None` if no valid item is
+/// found.
+pub fn parse_item<'t>(
+    source: &'t str,
+    tts: Vec<TT<'t>>,
+) -> (Option<Item<'t>>, Vec<HardSyntaxError<'t>>) {
+    parse_whole(source, tts, |p| {
+        if p.is_end() {
+            let loc = p.next_pos();
+            p.err(loc, "Expect an item");
+            None
+        } else {
+            p.eat_item()
+        }
+    })
+}
+
 /// Parser a `.rs` file. It will never fail, and source with grammar errors will
 /// be parsed into specific position (as TT) of AST.
 pub fn parse_crate<'t>(
@@ -80,6 +166,57 @@ pub fn parse_crate<'t>(
     (m, errs)
 }
 
+/// Lex and parse `source` as a `.rs` file. Lexical errors and unmatched
+/// delimiters stop parsing, while syntax errors are returned with the AST.
+///
+/// # Example
+///
+/// ```
+/// use mair::parse::parser::parse_source;
+/// let s = "fn f() {}\nstruct S(i32) }";
+/// let e = parse_source(s).unwrap_err();
+/// assert_eq!(e.line_col(s), (2, 15));
+/// assert_eq!(e.to_string(), "Unexpected close delimiter, found `}`");
+///
+/// let s = "fn f() {}\nstruct S(i32)";
+/// let (m, errs) = parse_source(s).unwrap();
+/// assert_eq!(m.items.len(), 2);
+/// assert_eq!(errs[0].line_col(s), (2, 14));
+/// assert_eq!(errs[0].expected, Some(String::from("a semicolon")));
+/// ```
+pub fn parse_source<'t>(
+    source: &'t str,
+) -> Result<(Mod<'t>, Vec<ParseError>), ParseError> {
+    parse_source_with_edition(source, Edition::default())
+}
+
+/// The same as `parse_source()` but lex `source` as `edition`.
+///
+/// # Example
+///
+/// ```
+/// use mair::parse::lexer::Edition;
+/// use mair::parse::parser::parse_source_with_edition;
+/// let s = "fn f(x: Box<dyn T>) { let async = 1; }";
+/// assert!(parse_source_with_edition(s, Edition::Rust2015).unwrap().1.is_empty());
+/// assert!(!parse_source_with_edition(s, Edition::Rust2018).unwrap().1.is_empty());
+/// ```
+pub fn parse_source_with_edition<'t>(
+    source:  &'t str,
+    edition: Edition,
+) -> Result<(Mod<'t>, Vec<ParseError>), ParseError> {
+    let toks = Lexer::new(source).edition(edition)
+                   .collect::<Result<Vec<_>, _>>()
+                   .map_err(|e| ParseError::from_lexical(source, &e))?;
+    let tts = parse_tts(source, &toks)
+                  .map_err(|e| ParseError::from_unmatched(source, &e))?;
+    let (m, errs) = parse_crate(source, tts);
+    let errs = errs.iter()
+                   .map(|e| ParseError::from_syntax(source, e))
+                   .collect();
+    Ok((m, errs))
+}
+
 // Helper macros
 macro_rules! tok {
     ($tok:pat) => { tok!($tok, _) };
@@ -100,6 +237,10 @@ macro_rules! ident {
     ($p:pat) => { ident!($p, _) };
     ($p:pat, $loc:pat) => { tok!(Tokk::Ident($p), $loc) };
 }
+/// A contextual keyword, which is never written as a raw identifier.
+macro_rules! ckw {
+    ($s:tt) => { ident!($s, $s) };
+}
 macro_rules! lt {
     ($p:pat) => { lt!($p, _) };
     ($p:pat, $loc:pat) => { tok!(Tokk::Lifetime($p), $loc) };
@@ -144,6 +285,7 @@ impl<'t, 'e> Parser<'t, 'e> {
         errs:   &'e mut Vec<HardSyntaxError<'t>>,
     ) -> Self {
         Parser {
+            source,
             tts: TTStream::new(tts, &source[..0]),
             errs,
         }
@@ -155,8 +297,9 @@ impl<'t, 'e> Parser<'t, 'e> {
         tts: Vec<TT<'t>>,
     ) -> Parser<'t, 'a> {
         Parser {
-            tts:  TTStream::new(tts, &loc[1..1]), // skip the first `(`
-            errs: self.errs,
+            source: self.source,
+            tts:    TTStream::new(tts, &loc[1..1]), // skip the first `(`
+            errs:   self.errs,
         }
     }
 
@@ -176,6 +319,22 @@ impl<'t, 'e> Parser<'t, 'e> {
         self.tts.prev_last_pos()
     }
 
+    /// Return an empty LocStr pointing at the beginning of next TT (if any),
+    /// or `self.prev_pos()`.
+    fn next_pos(&self) -> LocStr<'t> {
+        match self.tts.peek(0) {
+            Some(&(_, loc)) => &loc[..0],
+            None => self.prev_pos(),
+        }
+    }
+
+    /// Return the span from `begin` to the end of previous TT.
+    fn span_from(&self, begin: LocStr<'t>) -> Span {
+        let start = Span::of(self.source, begin).start;
+        let end = Span::of(self.source, self.prev_pos()).start;
+        Span{ start, end: if end < start { start } else { end } }
+    }
+
     /// Emit an error at `self.prev_pos()` with `reason`.
     fn err_prev(&mut self, reason: &'static str) {
         let loc = self.prev_pos();
@@ -224,7 +383,7 @@ impl<'t, 'e> Parser<'t, 'e> {
                     continue 'elem;
                 } else {
                     match_eat!{ self.tts;
-                        tok!(_, loc) => self.err(loc, "Expect a separator"),
+                        (_, loc) => self.err(loc, "Expect a separator"),
                         _ => unreachable!(), // not `end()`
                     }
                 }
@@ -254,7 +413,7 @@ impl<'t, 'e> Parser<'t, 'e> {
             while !end(self) { // until eating a `,` or reach the `end`
                 match_eat!{ self.tts;
                     sym!(",") => continue 'elem,
-                    tok!(_, loc) => self.err(loc, "Expect `,`"),
+                    (_, loc) => self.err(loc, "Expect `,`"),
                     _ => unreachable!(), // not `end`
                 }
             }
@@ -307,16 +466,37 @@ impl<'t, 'e> Parser<'t, 'e> {
             Some(&kw!("type")) |
             Some(&kw!("struct")) |
             Some(&kw!("enum")) |
-            Some(&kw!("const")) | Some(&kw!("static")) |
+            Some(&kw!("static")) |
             Some(&kw!("trait")) |
             Some(&kw!("impl")) =>
                 true,
+            Some(&kw!("const")) => match self.tts.peek(1) {
+                Some(&tree!(_, delim: Brace, ..)) => false, // `const {}`
+                _ => true,
+            },
             Some(&kw!("unsafe")) => match self.tts.peek(1) {
                 Some(&kw!("fn")) |
-                Some(&kw!("extern")) =>
+                Some(&kw!("extern")) |
+                Some(&kw!("trait")) |
+                Some(&ckw!("auto")) |
+                Some(&kw!("impl")) =>
                     true,
                 _ => false,
             },
+            Some(&ckw!("auto")) => match self.tts.peek(1) {
+                Some(&kw!("trait")) => true, // `auto` is a contextual keyword
+                _ => false,
+            },
+            Some(&ckw!("async")) |
+            Some(&kw!("async")) => self.is_fn_begin(),
+            Some(&ckw!("macro_rules")) => match self.tts.peek(1) {
+                Some(&sym!("!")) => true,
+                _ => false,
+            },
+            Some(&ckw!("union")) => match self.tts.peek(1) {
+                Some(&ident!(_)) => true, // `union` is a contextual keyword
+                _ => false,
+            },
             _ => false,
         }
     }
@@ -326,8 +506,11 @@ impl<'t, 'e> Parser<'t, 'e> {
         match self.tts.peek(0) {
             Some(&ident!(_)) |
             Some(&sym!("::")) |
+            Some(&kw!("crate")) | Some(&kw!("super")) | Some(&kw!("self")) |
+            Some(&sym!("<")) | Some(&sym!("<<")) |
             Some(&lit!(_)) |
             Some(&kw!("ref")) | Some(&kw!("mut")) |
+            Some(&sym!("&")) | Some(&sym!("&&")) |
             Some(&tree!(_, delim: Paren, ..)) =>
                 true,
             _ => false,
@@ -339,20 +522,27 @@ impl<'t, 'e> Parser<'t, 'e> {
         match self.tts.peek(0) {
             Some(&lit!(_)) |
             Some(&lt!(_)) | // 'a: loop {}
-            Some(&kw!("unsafe")) |
+            Some(&kw!("unsafe")) | Some(&kw!("const")) |
+            Some(&kw!("try")) |
+            Some(&kw!("crate")) |
             Some(&kw!("super")) |
             Some(&kw!("self")) |
             Some(&kw!("Self")) | // Self{..}
             Some(&ident!(_)) | Some(&sym!("::")) |
+            Some(&sym!("<")) | Some(&sym!("<<")) |
             Some(&tree!(_, ..)) |
             Some(&sym!("-")) | Some(&sym!("!")) |
-            Some(&sym!("&")) | Some(&sym!("*")) |
-            Some(&sym!("..")) |
+            Some(&sym!("&")) | Some(&sym!("&&")) | Some(&sym!("*")) |
+            Some(&sym!("..")) | Some(&sym!("..=")) |
             Some(&sym!("|")) | Some(&sym!("||")) | Some(&kw!("move")) |
             Some(&kw!("break")) | Some(&kw!("continue")) |
             Some(&kw!("loop")) | Some(&kw!("while")) | Some(&kw!("for")) |
             Some(&kw!("if")) | Some(&kw!("match")) | Some(&kw!("return")) =>
                 true,
+            Some(&kw!("async")) => matches!(
+                self.tts.peek(1),
+                Some(&kw!("move")) | Some(&tree!(_, delim: Brace, ..))
+            ),
             _ => false,
         }
     }
@@ -390,20 +580,58 @@ impl<'t, 'e> Parser<'t, 'e> {
         }
     }
 
+    /// Eat and return the visibility, like `pub`, `pub(crate)`,
+    /// `pub(in a::b)`. Return `Visibility::Private` if no `pub` is found.
+    /// In `pub (..)`, only `crate`, `super`, `self` and `in <path>` inside the
+    /// paren are recognized as the restriction, so a tuple struct element
+    /// like `pub (i32, i32)` is not misparsed.
+    fn eat_vis(&mut self) -> Visibility<'t> {
+        if !eatKw!(self.tts; "pub") {
+            return Visibility::Private;
+        }
+        match_eat!{ self.tts;
+            tree!(loc, delim: Paren, tts) => match tts.first() {
+                Some(&kw!("crate")) if tts.len() == 1 => Visibility::Crate,
+                Some(&kw!("super")) if tts.len() == 1 => Visibility::Super,
+                Some(&kw!("self")) if tts.len() == 1 => Visibility::SelfMod,
+                Some(&kw!("in")) => {
+                    let mut p = self.new_inner(loc, tts);
+                    p.tts.next(); // `in`
+                    let path = p.eat_path();
+                    p.expect_end();
+                    Visibility::InPath(path)
+                },
+                _ => {
+                    self.tts.putback((TTKind::Tree{ delim: Paren, tts }, loc));
+                    Visibility::Public
+                },
+            },
+            _ => Visibility::Public,
+        }
+    }
+
     /// Eat and return an identifier, or return the empty str slice after the
     /// last TT.
     fn eat_ident(&mut self) -> Ident<'t> {
         match_eat!{ self.tts;
-            ident!(s) => Ok(s),
+            ident!(_, s) => Ok(s),
             _ => Err(self.prev_pos()),
         }
     }
 
     /// Eat and return a Path.
     fn eat_path(&mut self) -> Path<'t> {
-        let is_absolute = match_eat!{ self.tts;
-            sym!("::") => true,
-            _ => false,
+        let qself = match_eat!{ self.tts;
+            sym!("<") => Some(Box::new(self.eat_qself_tail())),
+            sym!("<<", loc) => {
+                symBack!(self.tts, "<", loc);
+                Some(Box::new(self.eat_qself_tail()))
+            },
+            _ => None,
+        };
+        let root = match qself {
+            Some(_) => PathRoot::None,
+            None => self.eat_path_root(),
         };
         let mut comps = vec![self.eat_path_comp()];
         loop {
@@ -412,7 +640,45 @@ impl<'t, 'e> Parser<'t, 'e> {
                 _ => break,
             }
         }
-        Path{ is_absolute, comps }
+        Path{ qself, root, comps }
+    }
+
+    /// Eat and return the root of a path, like `::`, `crate::` or
+    /// `super::super::`.
+    fn eat_path_root(&mut self) -> PathRoot {
+        match_eat!{ self.tts;
+            sym!("::") => PathRoot::Global,
+            kw!("crate"), sym!("::") => PathRoot::Crate,
+            kw!("self"), sym!("::") => PathRoot::SelfMod,
+            kw!("super"), sym!("::") => {
+                let mut n = 1;
+                loop {
+                    match_eat!{ self.tts;
+                        kw!("super"), sym!("::") => n += 1,
+                        _ => break,
+                    }
+                }
+                PathRoot::Super(n)
+            },
+            _ => PathRoot::None,
+        }
+    }
+
+    /// Eat the tail after `<` of the qualified self type `<ty [as tr]>::`.
+    fn eat_qself_tail(&mut self) -> QSelf<'t> {
+        let ty = self.eat_ty(false);
+        let tr = match_eat!{ self.tts;
+            kw!("as") => Some(self.eat_ty_apply()),
+            _ => None,
+        };
+        if !self.try_eat_templ_end() {
+            self.err_prev("Expect `>`");
+        }
+        match_eat!{ self.tts;
+            sym!("::") => (),
+            _ => self.err_prev("Expect `::`"),
+        }
+        QSelf{ ty, tr }
     }
 
     /// Eat and return a path component.
@@ -425,11 +691,22 @@ impl<'t, 'e> Parser<'t, 'e> {
                 let name = self.eat_ident();
                 let hint = match_eat!{ self.tts;
                     sym!("::"), sym!("<") => {
+                        let max_order = Cell::new(0);
                         let (v, _) = self.eat_many_sep_tail(
                             symbol_type!(","),
-                            |p| match_eat!{ p.tts;
-                                lt!(lt) => TyHintArg::Lifetime(lt),
-                                _ => TyHintArg::Ty(p.eat_ty(true)),
+                            |p| {
+                                let begin = p.next_pos();
+                                let (arg, order) = match_eat!{ p.tts;
+                                    lt!(lt) => (TyHintArg::Lifetime(lt), 0),
+                                    _ => (match p.eat_opt_const_arg() {
+                                        Some(e) => TyHintArg::Const(e),
+                                        None => TyHintArg::Ty(p.eat_ty(true)),
+                                    }, 1),
+                                };
+                                p.check_generic_arg_order(
+                                    &max_order, order, begin,
+                                );
+                                arg
                             },
                             Parser::try_eat_templ_end,
                         );
@@ -448,12 +725,10 @@ impl<'t, 'e> Parser<'t, 'e> {
         loop {
             match_eat!{ self.tts;
                 tok!(Tokk::InnerDoc(doc), loc) =>
-                    v.push(Attr::Doc{ loc, doc }),
+                    v.push(Attr::Doc{ is_inner: true, loc, doc }),
                 sym!("#"), sym!("!"), tree!(loc, delim: Bracket, tts) => {
-                    let mut p = self.new_inner(loc, tts);
-                    let meta = p.eat_meta();
-                    p.expect_end();
-                    v.push(Attr::Meta(meta))
+                    let attr = self.eat_attr_content(true, loc, tts);
+                    v.push(attr)
                 },
                 _ => return v,
             }
@@ -466,21 +741,43 @@ impl<'t, 'e> Parser<'t, 'e> {
         loop {
             match_eat!{ self.tts;
                 tok!(Tokk::OuterDoc(doc), loc) =>
-                    v.push(Attr::Doc{ loc, doc }),
+                    v.push(Attr::Doc{ is_inner: false, loc, doc }),
                 sym!("#"), tree!(loc, delim: Bracket, tts) => {
-                    let mut p = self.new_inner(loc, tts);
-                    let meta = p.eat_meta();
-                    p.expect_end();
-                    v.push(Attr::Meta(meta))
+                    let attr = self.eat_attr_content(false, loc, tts);
+                    v.push(attr)
                 },
                 _ => return v,
             }
         }
     }
 
+    /// Parse `tts` inside `#[]` in `loc` as a meta. If it is not a valid
+    /// meta, keep the TTs after the path instead.
+    fn eat_attr_content(
+        &mut self,
+        is_inner: bool,
+        loc: LocStr<'t>,
+        tts: Vec<TT<'t>>,
+    ) -> Attr<'t> {
+        let mut errs = vec![];
+        let meta = {
+            let mut p = Parser::new(self.source, tts.clone(), &mut errs);
+            let meta = p.eat_meta();
+            p.expect_end();
+            meta
+        };
+        if errs.is_empty() {
+            return Attr::Meta{ is_inner, meta };
+        }
+        let mut p = self.new_inner(loc, tts);
+        let name = p.eat_path();
+        let tts = p.tts.by_ref().collect();
+        Attr::Tokens{ is_inner, name, tts }
+    }
+
     /// Eat a valid meta.
     fn eat_meta(&mut self) -> Meta<'t> {
-        let name = self.eat_ident();
+        let name = self.eat_path();
         match_eat!{ self.tts;
             sym!("="), lit!(value) =>
                 Meta::KeyValue{ key: name, value },
@@ -498,21 +795,61 @@ impl<'t, 'e> Parser<'t, 'e> {
     /// Eat an item. It will consume at lease one TT.
     /// Warning: There must be at least one TT left.
     fn eat_item(&mut self) -> Option<Item<'t>> {
-        let mut attrs = self.eat_outer_attrs();
-        let is_pub = eatKw!(self.tts; "pub");
+        let begin = self.next_pos();
+        let attrs = self.eat_outer_attrs();
+        self.eat_item_after_attrs(begin, attrs)
+    }
+
+    /// Eat an item whose outer attributes `attrs` are already eaten from
+    /// `begin`.
+    fn eat_item_after_attrs(
+        &mut self,
+        begin:     LocStr<'t>,
+        mut attrs: Vec<Attr<'t>>,
+    ) -> Option<Item<'t>> {
+        let vis = self.eat_vis();
         let opt_detail = self.eat_opt_item_detail(&mut attrs);
         let detail = match opt_detail {
             Some(x) => x,
-            None if attrs.is_empty() && !is_pub => {
+            None if attrs.is_empty() && vis == Visibility::Private => {
                 // havn't consumed `pub`
                 // "consumes nothing and nothing left" is impossible.
-                let (_, loc) = self.tts.next().unwrap(); // here <-/
+                let loc = self.skip_bad_item(); // here <-/
                 self.err(loc, "Unknow beginning of item");
                 return None
             },
             None => return None,
         };
-        Some(Item{ attrs, is_pub, detail })
+        let span = self.span_from(begin);
+        Some(Item{ attrs, vis, detail, span })
+    }
+
+    /// Skip TTs to recover from a bad item, until the next possible beginning
+    /// of item or after a `;`. Return the location of skipped TTs. It will
+    /// consume at least one TT.
+    /// Warning: There must be at least one TT left.
+    fn skip_bad_item(&mut self) -> LocStr<'t> {
+        let (tt, first) = self.tts.next().unwrap();
+        if let TTKind::Token(Tokk::Symbol(symbol_type!(";"))) = tt {
+            return first;
+        }
+        let mut last = first;
+        loop {
+            match self.tts.peek(0) {
+                None |
+                Some(&sym!("#")) |
+                Some(&tok!(Tokk::OuterDoc(_))) => break,
+                _ if self.is_item_begin() || self.is_plugin_invoke_begin() =>
+                    break,
+                _ => (),
+            }
+            let (tt, loc) = self.tts.next().unwrap();
+            last = loc;
+            if let TTKind::Token(Tokk::Symbol(symbol_type!(";"))) = tt {
+                break;
+            }
+        }
+        str_range(self.source, first, last)
     }
 
     /// Eat and return the detail of an item, or return None.
@@ -521,65 +858,90 @@ impl<'t, 'e> Parser<'t, 'e> {
         attrs: &mut Vec<Attr<'t>>,
     ) -> Option<ItemKind<'t>> {
         if let Some(p) = self.eat_opt_plugin_invoke() {
+            if let Some(Ok("macro_rules")) = p.name.as_ident() {
+                return Some(self.eat_macro_def_tail(p.ident, p.tt));
+            }
+            match p.tt {
+                tree!(_, delim: Brace, ..) => (),
+                _ => self.expect_semi(),
+            }
             return Some(ItemKind::PluginInvoke(p));
         }
+        if let Some(f) = self.eat_fn_item(attrs) {
+            return Some(f);
+        }
         match_eat!{ self.tts;
             kw!("extern"), kw!("crate") => Some(self.eat_extern_crate_tail()),
             kw!("use") => Some(self.eat_use_tail()),
             kw!("mod") => Some(self.eat_mod_tail(attrs)),
-            kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, false, ABI::Normal)),
-            kw!("extern"), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, false, ABI::Extern)),
-            kw!("extern"), lit_str!(abi, loc), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, false, ABI::Specific{ loc, abi })),
-            kw!("unsafe"), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, true, ABI::Normal)),
-            kw!("unsafe"), kw!("extern"), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, true, ABI::Extern)),
-            kw!("unsafe"), kw!("extern"), lit_str!(abi, loc), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, true, ABI::Specific{ loc
-                                                                    , abi })),
             kw!("extern") => Some(self.eat_extern_tail(attrs)),
             kw!("type")   => Some(self.eat_type_tail()),
             kw!("struct") => Some(self.eat_struct_tail()),
+            ckw!("union"), ident!(_, name) =>
+                Some(self.eat_union_tail(Ok(name))),
             kw!("enum")   => Some(self.eat_enum_tail()),
             kw!("const")  => Some(self.eat_const_static_tail(false)),
             kw!("static") => Some(self.eat_const_static_tail(true)),
-            kw!("trait")  => Some(self.eat_trait_tail()),
-            kw!("impl")   => Some(self.eat_impl_tail(attrs)),
+            kw!("trait")  => Some(self.eat_trait_tail(attrs, false, false)),
+            kw!("unsafe"), kw!("trait") =>
+                Some(self.eat_trait_tail(attrs, true, false)),
+            ckw!("auto"), kw!("trait") =>
+                Some(self.eat_trait_tail(attrs, false, true)),
+            kw!("unsafe"), ckw!("auto"), kw!("trait") =>
+                Some(self.eat_trait_tail(attrs, true, true)),
+            kw!("impl")   => Some(self.eat_impl_tail(attrs, false)),
+            kw!("unsafe"), kw!("impl") =>
+                Some(self.eat_impl_tail(attrs, true)),
             _ => None,
         }
     }
 
+    /// Eat the tail after `macro_rules! <name> <body>`.
+    fn eat_macro_def_tail(
+        &mut self,
+        name: Option<Ident<'t>>,
+        body: TT<'t>,
+    ) -> ItemKind<'t> {
+        let name = name.unwrap_or_else(|| {
+            let loc = &body.1[..0]; // before the body
+            self.err(loc, "Expect the macro name");
+            Err(loc)
+        });
+        match body {
+            tree!(_, delim: Brace, ..) => (),
+            _ => self.expect_semi(),
+        }
+        ItemKind::MacroDef{ name, body }
+    }
+
     /// Eat the tail after `extern crate`.
     fn eat_extern_crate_tail(&mut self) -> ItemKind<'t> {
-        let name = self.eat_ident();
+        let name = match_eat!{ self.tts;
+            kw!("self", loc) => Ok(loc),
+            _ => self.eat_ident(),
+        };
+        let alias = match_eat!{ self.tts;
+            kw!("as") => Some(self.eat_ident()),
+            _ => None,
+        };
         self.expect_semi();
-        ItemKind::ExternCrate{ name }
+        ItemKind::ExternCrate{ name, alias }
     }
 
     /// Eat the tail after `use`.
     fn eat_use_tail(&mut self) -> ItemKind<'t> {
-        let supers = match_eat!{ self.tts;
-            kw!("self"), sym!("::") => Some(0),
-            kw!("super"), sym!("::") => {
-                let mut n = 1;
-                loop {
-                    match_eat!{ self.tts;
-                        kw!("super"), sym!("::") => n += 1,
-                        _ => break,
-                    }
-                }
-                Some(n)
-            },
-            sym!("::") => None,
-            _ => None,
-        };
+        let tree = self.eat_use_tree();
+        self.expect_semi();
+        ItemKind::Use(tree)
+    }
+
+    /// Eat and return a tree in `use`, like `a::b::{c, d::*}`.
+    fn eat_use_tree(&mut self) -> UseTree<'t> {
+        let root = self.eat_path_root();
         let mut comps = vec![];
         loop {
             match_eat!{ self.tts;
-                ident!(name), sym!("::") =>
+                ident!(_, name), sym!("::") =>
                     comps.push(Ok(name)),
                 sym!("::") => {
                     let loc = self.prev_pos();
@@ -588,34 +950,22 @@ impl<'t, 'e> Parser<'t, 'e> {
                 _ => break,
             }
         }
-        let use_path = match supers {
-            Some(supers) => UsePath::Relative{ supers, comps },
-            None         => UsePath::Absolute{ comps },
-        };
-        self.eat_use_names_tail(use_path)
-    }
-
-    /// Eat the using names and semicolon in item `use`, return the ItemKind.
-    fn eat_use_names_tail(&mut self, path: UsePath<'t>) -> ItemKind<'t> {
-        match_eat!{ self.tts;
-            sym!("*") => {
-                self.expect_semi();
-                ItemKind::UseAll{ path }
-            },
+        let sub = match_eat!{ self.tts;
+            sym!("*") => UseTree::Glob,
             tree!(loc, delim: Brace, tts) => {
-                let (names, _) = self.new_inner(loc, tts)
+                let (trees, _) = self.new_inner(loc, tts)
                                      .eat_many_comma_tail_end(
-                    Parser::eat_use_name,
+                    Parser::eat_use_tree,
                 );
-                self.expect_semi();
-                ItemKind::UseSome{ path, names }
-            },
-            _ => {
-                let name = self.eat_use_name();
-                self.expect_semi();
-                ItemKind::UseOne{ path, name }
+                UseTree::Group(trees)
             },
+            _ => UseTree::Name(self.eat_use_name()),
+        };
+        if root == PathRoot::None && comps.is_empty() {
+            return sub;
         }
+        let path = UsePath{ root, comps };
+        UseTree::Path{ path, sub: Box::new(sub) }
     }
 
     /// Eat and return a UseName like `self` or `name [as alias]`.
@@ -648,14 +998,13 @@ impl<'t, 'e> Parser<'t, 'e> {
         }
     }
 
-    /// Eat the tail after `[unsafe] [extern [<abt>]] fn`.
+    /// Eat the tail after `<qualifiers> fn`.
     fn eat_fn_tail(
         &mut self,
-        attrs:     &mut Vec<Attr<'t>>,
-        is_unsafe: bool,
-        abi:       ABI<'t>,
+        attrs: &mut Vec<Attr<'t>>,
+        quals: FnQuals<'t>,
     ) -> ItemKind<'t> {
-        let sig = Box::new(self.eat_fn_sig(is_unsafe, abi));
+        let sig = Box::new(self.eat_fn_sig(quals));
         match self.eat_block_expr() {
             Expr::Block{ attrs: mut inner_attrs, stmts, ret } => {
                 attrs.append(&mut inner_attrs);
@@ -675,39 +1024,42 @@ impl<'t, 'e> Parser<'t, 'e> {
     /// parens, it will emit an error.
     fn eat_param_list_end(
         &mut self,
-    ) -> (Vec<FuncParam<'t>>, bool) {
+    ) -> (Vec<FuncParam<'t>>, Option<LocStr<'t>>) {
         match_eat!{ self.tts;
             tree!(loc, delim: Paren, tts) => {
-                let (args, va_) = self.new_inner(loc, tts)
-                                     .eat_many_comma_tail_last(
+                self.new_inner(loc, tts).eat_many_comma_tail_last(
                     Parser::eat_func_param,
                     |p| match_eat!{ p.tts;
-                        sym!("...") => Some(()),
+                        sym!("...", loc) => Some(loc),
                         _ => None,
                     },
                     |p| p.is_end(),
-                );
-                (args, va_.is_some())
+                )
             },
             _ => {
                 self.err_prev("Expect the parameter list");
-                (vec![], false)
+                (vec![], None)
             },
         }
     }
 
     /// Eat and return the signature of a function.
-    fn eat_fn_sig(
-        &mut self,
-        is_unsafe: bool,
-        abi:       ABI<'t>,
-    ) -> FuncSig<'t> {
+    fn eat_fn_sig(&mut self, quals: FnQuals<'t>) -> FuncSig<'t> {
+        let FnQuals{ begin, is_const, is_async, is_unsafe, abi } = quals;
         let name = self.eat_ident();
         let templ = self.eat_templ();
-        let (args, is_va) = self.eat_param_list_end();
+        let (args, va_) = self.eat_param_list_end();
+        if let Some(loc) = va_ {
+            self.err(loc, "`...` is only allowed in `extern` blocks");
+        }
+        let is_va = va_.is_some();
         let ret_ty = self.eat_opt_ret_ty();
         let whs = self.eat_opt_whs();
-        FuncSig{ is_unsafe, abi, name, templ, args, is_va, ret_ty, whs }
+        let span = self.span_from(begin);
+        FuncSig{
+            is_const, is_async, is_unsafe, abi,
+            name, templ, args, is_va, ret_ty, whs, span,
+        }
     }
 
     /// Eat and return a parameter of function.
@@ -772,26 +1124,31 @@ impl<'t, 'e> Parser<'t, 'e> {
     ///
     /// Warning: Never call it when `is_end()`.
     fn eat_extern_item(&mut self) -> Option<ExternItem<'t>> {
+        let begin = self.next_pos();
         let attrs = self.eat_outer_attrs();
-        let is_pub = eatKw!(self.tts; "pub");
+        let vis = self.eat_vis();
         match_eat!{ self.tts;
             kw!("fn") => {
                 let name = self.eat_ident();
-                let (args, is_va) = self.eat_param_list_end();
+                let (args, va_) = self.eat_param_list_end();
+                let is_va = va_.is_some();
                 let ret_ty = self.eat_opt_ret_ty();
                 self.expect_semi();
                 let detail = ExternItemKind::Func{ name, args, is_va, ret_ty };
-                Some(ItemWrap{ attrs, is_pub, detail })
+                let span = self.span_from(begin);
+                Some(ItemWrap{ attrs, vis, detail, span })
             },
             kw!("static") => {
+                let is_mut = eatKw!(self.tts; "mut");
                 let name = self.eat_ident();
                 let ty = match_eat!{ self.tts;
                     sym!(":") => Some(Box::new(self.eat_ty(true))),
                     _ => None,
                 };
                 self.expect_semi();
-                let detail = ExternItemKind::Static{ name, ty };
-                Some(ItemWrap{ attrs, is_pub, detail })
+                let detail = ExternItemKind::Static{ is_mut, name, ty };
+                let span = self.span_from(begin);
+                Some(ItemWrap{ attrs, vis, detail, span })
             },
             tok!(_, loc) => {
                 self.err(loc, "Expect `fn` or `static`");
@@ -853,18 +1210,39 @@ impl<'t, 'e> Parser<'t, 'e> {
         }
     }
 
+    /// Eat the tail after `union <name>`.
+    fn eat_union_tail(&mut self, name: Ident<'t>) -> ItemKind<'t> {
+        let templ = self.eat_templ();
+        let whs = self.eat_opt_whs();
+        let fields = match_eat!{ self.tts;
+            tree!(loc, delim: Brace, tts) => {
+                let (v, _) = self.new_inner(loc, tts)
+                                 .eat_many_comma_tail_end(
+                    Parser::eat_struct_field,
+                );
+                v
+            },
+            _ => {
+                self.err_prev("Expect the union body `{}`");
+                vec![]
+            },
+        };
+        ItemKind::Union{ name, templ, whs, fields }
+    }
+
     /// Eat and return an element of tuple-like-struct.
     fn eat_struct_tuple_elem(&mut self) -> StructTupleElem<'t> {
         let attrs = self.eat_outer_attrs();
-        let is_pub = eatKw!(self.tts; "pub");
+        let vis = self.eat_vis();
         let ty = self.eat_ty(true);
-        StructTupleElem{ attrs, is_pub, ty }
+        StructTupleElem{ attrs, vis, ty }
     }
 
     /// Eat and return a field of struct with fields.
     fn eat_struct_field(&mut self) -> StructField<'t> {
+        let begin = self.next_pos();
         let attrs = self.eat_outer_attrs();
-        let is_pub = eatKw!(self.tts; "pub");
+        let vis = self.eat_vis();
         let name = self.eat_ident();
         let ty = match_eat!{ self.tts;
             sym!(":") => self.eat_ty(true),
@@ -873,7 +1251,8 @@ impl<'t, 'e> Parser<'t, 'e> {
                 Ty::Error
             },
         };
-        StructField{ attrs, is_pub, name, ty }
+        let span = self.span_from(begin);
+        StructField{ attrs, vis, name, ty, span }
     }
 
     /// Eat the tail after `enum`.
@@ -901,7 +1280,7 @@ impl<'t, 'e> Parser<'t, 'e> {
     fn eat_enum_var(&mut self) -> EnumVar<'t> {
         let attrs = self.eat_outer_attrs();
         let name = self.eat_ident();
-        match_eat!{ self.tts;
+        let var = match_eat!{ self.tts;
             tree!(loc, delim: Paren, tts) => {
                 let (elems, _) = self.new_inner(loc, tts)
                                      .eat_many_comma_tail_end(
@@ -916,12 +1295,23 @@ impl<'t, 'e> Parser<'t, 'e> {
                 );
                 EnumVar::Struct{ attrs, name, fields }
             },
-            _ => EnumVar::Unit{ attrs, name },
+            sym!("=") => {
+                let disc = Some(Box::new(self.eat_expr(false, true)));
+                return EnumVar::Unit{ attrs, name, disc };
+            },
+            _ => EnumVar::Unit{ attrs, name, disc: None },
+        };
+        if let Some(&sym!("=", loc)) = self.tts.peek(0) {
+            self.err(loc, "Discriminants are only allowed on unit variants");
+            self.tts.next();
+            self.eat_expr(false, true);
         }
+        var
     }
 
     /// Eat the tail after `const` or `static`.
     fn eat_const_static_tail(&mut self, is_static: bool) -> ItemKind<'t> {
+        let is_mut = is_static && eatKw!(self.tts; "mut");
         let name = self.eat_ident();
         let ty = match_eat!{ self.tts;
             sym!(":") => Box::new(self.eat_ty(true)),
@@ -939,65 +1329,121 @@ impl<'t, 'e> Parser<'t, 'e> {
         };
         self.expect_semi();
         if is_static {
-            ItemKind::Static{ name, ty, val }
+            ItemKind::Static{ is_mut, name, ty, val }
         } else {
             ItemKind::Const{ name, ty, val }
         }
     }
 
-    /// Eat the tail after `trait`.
-    fn eat_trait_tail(&mut self) -> ItemKind<'t> {
+    /// Eat the tail after `const <name>` in a trait or impl, return the type
+    /// and the optional value, which is required if `need_val` is true.
+    fn eat_assoc_const_tail(
+        &mut self,
+        need_val: bool,
+    ) -> (Box<Ty<'t>>, Option<Box<Expr<'t>>>) {
+        let ty = match_eat!{ self.tts;
+            sym!(":") => Box::new(self.eat_ty(true)),
+            _ => {
+                self.err_prev("Expect `: <type>`");
+                Box::new(Ty::Error)
+            },
+        };
+        let val = match_eat!{ self.tts;
+            sym!("=") => Some(Box::new(self.eat_expr(false, true))),
+            _ => {
+                if need_val {
+                    self.err_prev("Expect `= <expr>`");
+                }
+                None
+            },
+        };
+        self.expect_semi();
+        (ty, val)
+    }
+
+    /// Eat the tail after `[unsafe] [auto] trait`.
+    fn eat_trait_tail(
+        &mut self,
+        attrs:     &mut Vec<Attr<'t>>,
+        is_unsafe: bool,
+        is_auto:   bool,
+    ) -> ItemKind<'t> {
         let name = self.eat_ident();
         let templ = self.eat_templ();
         let base = match_eat!{ self.tts;
-            sym!(":") => Some(Box::new(self.eat_ty(true))),
+            sym!(":") => Some(self.eat_bounds()),
             _ => None,
         };
         let whs = self.eat_opt_whs();
         let items = match_eat!{ self.tts;
-            tree!(loc, delim: Brace, tts) => {
-                let mut items = vec![];
-                let mut p = self.new_inner(loc, tts);
-                while !p.is_end() {
-                    if let Some(x) = p.eat_trait_item() {
-                        items.push(x);
-                    }
-                }
-                items
-            },
+            tree!(loc, delim: Brace, tts) =>
+                self.new_inner(loc, tts).eat_mod_like_end(
+                    attrs,
+                    |p| p.eat_trait_item(),
+                ),
             _ => {
                 self.err_prev("Expect the body in `{}`");
                 vec![]
             },
         };
-        ItemKind::Trait{ name, templ, base, whs, items }
+        ItemKind::Trait{ is_unsafe, is_auto, name, templ, base, whs, items }
+    }
+
+    /// Return whether the next TTs are `[const] [async] [unsafe]
+    /// [extern [<abi>]] fn`.
+    fn is_fn_begin(&self) -> bool {
+        let mut i = 0;
+        if let Some(&kw!("const")) = self.tts.peek(i) {
+            i += 1;
+        }
+        match self.tts.peek(i) {
+            Some(&ckw!("async")) | // a contextual keyword in Rust 2015
+            Some(&kw!("async")) => i += 1,
+            _ => (),
+        }
+        if let Some(&kw!("unsafe")) = self.tts.peek(i) {
+            i += 1;
+        }
+        if let Some(&kw!("extern")) = self.tts.peek(i) {
+            i += 1;
+            if let Some(&lit_str!(_)) = self.tts.peek(i) {
+                i += 1;
+            }
+        }
+        match self.tts.peek(i) {
+            Some(&kw!("fn")) => true,
+            _ => false,
+        }
     }
 
-    /// Eat `fn` item like `[unsafe] [extern [<abi>]] fn ...`. Return None if
-    /// fails.
+    /// Eat `fn` item like `[const] [async] [unsafe] [extern [<abi>]] fn ...`.
+    /// Return None without eating anything if the next TTs are not a function.
     fn eat_fn_item(
         &mut self,
         attrs: &mut Vec<Attr<'t>>,
     ) -> Option<ItemKind<'t>> {
+        if !self.is_fn_begin() {
+            return None;
+        }
+        let begin = self.next_pos();
+        let is_const = eatKw!(self.tts; "const");
+        let is_async = match_eat!{ self.tts;
+            ckw!("async") => true,
+            kw!("async") => true,
+            _ => false,
+        };
+        let is_unsafe = eatKw!(self.tts; "unsafe");
+        let abi = match_eat!{ self.tts;
+            kw!("extern"), lit_str!(abi, loc) => ABI::Specific{ loc, abi },
+            kw!("extern") => ABI::Extern,
+            _ => ABI::Normal,
+        };
         match_eat!{ self.tts;
-            kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, false, ABI::Normal)),
-            kw!("extern"), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, false, ABI::Extern)),
-            kw!("extern"), lit_str!(abi, loc), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs,
-                                      false,
-                                      ABI::Specific{ loc, abi })),
-            kw!("unsafe"), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, true, ABI::Normal)),
-            kw!("unsafe"), kw!("extern"), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs, true, ABI::Extern)),
-            kw!("unsafe"), kw!("extern"), lit_str!(abi, loc), kw!("fn") =>
-                Some(self.eat_fn_tail(attrs,
-                                      true,
-                                      ABI::Specific{ loc, abi })),
-            _ => None,
+            kw!("fn") => (),
+            _ => unreachable!(), // checked by `is_fn_begin()`
         }
+        let quals = FnQuals{ begin, is_const, is_async, is_unsafe, abi };
+        Some(self.eat_fn_tail(attrs, quals))
     }
 
     /// Eat and return an item inside `trait` block. It will consume at least
@@ -1005,24 +1451,40 @@ impl<'t, 'e> Parser<'t, 'e> {
     ///
     /// Warning: Never call it when `is_end()`.
     fn eat_trait_item(&mut self) -> Option<TraitItem<'t>> {
+        let begin = self.next_pos();
         let mut attrs = self.eat_outer_attrs();
-        let is_pub = eatKw!(self.tts; "pub");
+        let vis = self.eat_vis();
         match_eat!{ self.tts;
             kw!("type") => {
                 let name = self.eat_ident();
+                let templ = self.eat_templ();
+                let bound = match_eat!{ self.tts;
+                    sym!(":") => Some(self.eat_bounds()),
+                    _ => None,
+                };
+                let whs = self.eat_opt_whs();
                 let default = match_eat!{ self.tts;
                     sym!("=") => Some(Box::new(self.eat_ty(true))),
                     _ => None,
                 };
-                self.expect_semi();
-                let detail = TraitItemKind::AssocTy{ name, default };
-                Some(ItemWrap{ attrs, is_pub, detail })
+                let whs = self.eat_assoc_ty_whs_end(whs);
+                let detail = TraitItemKind::AssocTy{
+                    name, templ, bound, whs, default,
+                };
+                let span = self.span_from(begin);
+                Some(ItemWrap{ attrs, vis, detail, span })
+            },
+            kw!("const"), ident!(_, name) => {
+                let (ty, default) = self.eat_assoc_const_tail(false);
+                let detail = TraitItemKind::Const{ name: Ok(name), ty, default };
+                let span = self.span_from(begin);
+                Some(ItemWrap{ attrs, vis, detail, span })
             },
             _ => {
                 match self.eat_fn_item(&mut attrs) {
                     None => match_eat!{ self.tts;
                         tok!(_, loc) => {
-                            self.err(loc, "Expect a `type` or `fn` item`");
+                            self.err(loc, "Expect a `type`, `const` or `fn` item");
                             None
                         },
                         _ => unreachable!(), // not `is_end`
@@ -1032,11 +1494,13 @@ impl<'t, 'e> Parser<'t, 'e> {
                             sig,
                             default: Some(body),
                         };
-                        Some(ItemWrap{ attrs, is_pub, detail })
+                        let span = self.span_from(begin);
+                        Some(ItemWrap{ attrs, vis, detail, span })
                     },
                     Some(ItemKind::FuncDecl{ sig }) => {
                         let detail = TraitItemKind::Func{ sig, default: None };
-                        Some(ItemWrap{ attrs, is_pub, detail })
+                        let span = self.span_from(begin);
+                        Some(ItemWrap{ attrs, vis, detail, span })
                     },
                     Some(_) => unreachable!(), // eat_fn_tail() never return
                                                // others
@@ -1046,8 +1510,26 @@ impl<'t, 'e> Parser<'t, 'e> {
     }
 
     /// Eat the tail after `impl`.
-    fn eat_impl_tail(&mut self, attrs: &mut Vec<Attr<'t>>) -> ItemKind<'t> {
+    fn eat_impl_tail(
+        &mut self,
+        attrs:     &mut Vec<Attr<'t>>,
+        is_unsafe: bool,
+    ) -> ItemKind<'t> {
         let templ = self.eat_templ();
+        let const_loc = match_eat!{ self.tts;
+            kw!("const", loc) => Some(loc), // `impl const Trait for ..`
+            _ => None,
+        };
+        let neg = match_eat!{ self.tts;
+            sym!("!", loc) => if self.is_ty_apply_begin() {
+                Some(loc) // `impl !Trait for ..`
+            } else {
+                let bang = Tokk::Symbol(symbol_type!("!"));
+                self.tts.putback((TTKind::Token(bang), loc));
+                None // `impl ! { .. }`
+            },
+            _ => None,
+        };
         let ty = Box::new(self.eat_ty(true));
         let mut block = |p: &mut Self| match_eat!{ p.tts;
             tree!(loc, delim: Brace, tts) =>
@@ -1065,25 +1547,57 @@ impl<'t, 'e> Parser<'t, 'e> {
                 let tr = ty;
                 let ty = Box::new(self.eat_ty(true));
                 let whs = self.eat_opt_whs();
-                ItemKind::ImplTrait{ templ, tr, ty, whs, items: block(self) }
+                let is_const = const_loc.is_some();
+                let is_neg = neg.is_some();
+                ItemKind::ImplTrait{
+                    is_unsafe, templ, is_const, is_neg, tr, ty, whs,
+                    items: block(self),
+                }
             },
             _ => {
+                if let Some(loc) = neg {
+                    self.err(loc, "Negative impls must be trait impls");
+                }
+                if let Some(loc) = const_loc {
+                    self.err(loc, "Const impls must be trait impls");
+                }
                 let whs = self.eat_opt_whs();
-                ItemKind::ImplType{ templ, ty, whs, items: block(self) }
+                ItemKind::ImplType{
+                    is_unsafe, templ, ty, whs,
+                    items: block(self),
+                }
             },
         }
     }
 
+    /// Eat the `where` clause after the type of an associated type if `whs`
+    /// before the type is None, and then eat `;`.
+    fn eat_assoc_ty_whs_end(&mut self, whs: OptWhere<'t>) -> OptWhere<'t> {
+        let whs = match whs {
+            None => self.eat_opt_whs(),
+            Some(_) => whs,
+        };
+        self.expect_semi();
+        whs
+    }
+
     /// Eat and return an item inside `impl` block. It will consume at least
     /// one TT.
     ///
     /// Warning: Never call it when `is_end()`.
     fn eat_impl_item(&mut self) -> Option<ImplItem<'t>> {
+        let begin = self.next_pos();
         let mut attrs = self.eat_outer_attrs();
-        let is_pub = eatKw!(self.tts; "pub");
+        let vis = self.eat_vis();
+        let is_default = match_eat!{ self.tts;
+            ckw!("default") => true, // a contextual keyword here
+            _ => false,
+        };
         match_eat!{ self.tts;
             kw!("type") => {
                 let name = self.eat_ident();
+                let templ = self.eat_templ();
+                let whs = self.eat_opt_whs();
                 let val = match_eat!{ self.tts;
                     sym!("=") => self.eat_ty(true),
                     _ => {
@@ -1091,31 +1605,57 @@ impl<'t, 'e> Parser<'t, 'e> {
                         Ty::Error
                     },
                 };
-                self.expect_semi();
-                let detail = ImplItemKind::AssocTy{ name, val: Box::new(val) };
-                Some(ItemWrap{ attrs, is_pub, detail })
+                let whs = self.eat_assoc_ty_whs_end(whs);
+                let detail = ImplItemKind::AssocTy{
+                    is_default,
+                    name,
+                    templ,
+                    val: Box::new(val),
+                    whs,
+                };
+                let span = self.span_from(begin);
+                Some(ItemWrap{ attrs, vis, detail, span })
+            },
+            kw!("const"), ident!(_, name) => {
+                let (ty, val) = self.eat_assoc_const_tail(true);
+                let val = val.unwrap_or_else(|| Box::new(Expr::Error));
+                let detail = ImplItemKind::Const{
+                    is_default,
+                    name: Ok(name),
+                    ty,
+                    val,
+                };
+                let span = self.span_from(begin);
+                Some(ItemWrap{ attrs, vis, detail, span })
             },
             _ => {
                 match self.eat_fn_item(&mut attrs) {
                     None => match_eat!{ self.tts;
                         tok!(_, loc) => {
-                            self.err(loc, "Expect a `type` or `fn` item`");
+                            self.err(loc, "Expect a `type`, `const` or `fn` item");
                             None
                         },
                         _ => unreachable!(), // not `is_end`
                     },
                     Some(ItemKind::Func{ sig, body }) => {
                         let detail = ImplItemKind::Func{
+                            is_default,
                             sig,
                             body,
                         };
-                        Some(ItemWrap{ attrs, is_pub, detail })
+                        let span = self.span_from(begin);
+                        Some(ItemWrap{ attrs, vis, detail, span })
                     },
                     Some(ItemKind::FuncDecl{ sig }) => {
                         self.err_prev("Expect the function body");
                         let body = Box::new(Expr::Error);
-                        let detail = ImplItemKind::Func{ sig, body };
-                        Some(ItemWrap{ attrs, is_pub, detail })
+                        let detail = ImplItemKind::Func{
+                            is_default,
+                            sig,
+                            body,
+                        };
+                        let span = self.span_from(begin);
+                        Some(ItemWrap{ attrs, vis, detail, span })
                     },
                     Some(_) => unreachable!(), // eat_fn_tail() never return
                                                // others
@@ -1167,13 +1707,35 @@ impl<'t, 'e> Parser<'t, 'e> {
                 };
                 TemplArg::Lifetime{ name, bound }
             },
+            kw!("const") => {
+                let name = self.eat_ident();
+                let ty = match_eat!{ self.tts;
+                    sym!(":") => self.eat_ty(false),
+                    _ => {
+                        self.err_prev("Expect `: <type>`");
+                        Ty::Error
+                    },
+                };
+                // Only literals, paths and blocks are allowed as default
+                // values, so `>` will never be eaten as an operator.
+                let default = match_eat!{ self.tts;
+                    sym!("=") =>
+                        Some(Box::new(self.eat_expr_prefix(false, false))),
+                    _ => None,
+                };
+                TemplArg::Const{ name, ty: Box::new(ty), default }
+            },
             _ => {
                 let name = self.eat_ident();
                 let bound = match_eat!{ self.tts;
-                    sym!(":") => Some(self.eat_ty(true)),
+                    sym!(":") => Some(self.eat_bounds()),
+                    _ => None,
+                };
+                let default = match_eat!{ self.tts;
+                    sym!("=") => Some(Box::new(self.eat_ty(false))),
                     _ => None,
                 };
-                TemplArg::Ty{ name, bound }
+                TemplArg::Ty{ name, bound, default }
             },
         }
     }
@@ -1195,7 +1757,26 @@ impl<'t, 'e> Parser<'t, 'e> {
         v
     }
 
+    /// Eat and return bounds `Tr1 + 'a + ..` of a type, which may be empty.
+    /// A trailing `+` is allowed.
+    fn eat_bounds(&mut self) -> Vec<Bound<'t>> {
+        let (v, _) = self.eat_many_sep_tail(
+            symbol_type!("+"),
+            |p| match_eat!{ p.tts;
+                lt!(x) => Bound::Lifetime(x),
+                _ => Bound::Trait(p.eat_poly_trait()),
+            },
+            |p| match p.tts.peek(0) {
+                Some(&lt!(_)) |
+                Some(&sym!("+")) => false,
+                _ => !p.is_poly_trait_begin(),
+            },
+        );
+        v
+    }
+
     /// Eat and return `where` clause, or return None.
+    /// The clause ends before `{}`, `->`, `;`, `=` (in item `type`) or EOF.
     fn eat_opt_whs(&mut self) -> OptWhere<'t> {
         match_eat!{ self.tts;
             kw!("where") => {
@@ -1203,9 +1784,11 @@ impl<'t, 'e> Parser<'t, 'e> {
                     symbol_type!(","),
                     Parser::eat_restrict,
                     |p| match p.tts.peek(0) {
+                        None |
                         Some(&tree!(_, delim: Brace, ..)) |
                         Some(&sym!("->")) |
-                        Some(&sym!(";")) => true,
+                        Some(&sym!(";")) |
+                        Some(&sym!("=")) => true,
                         _ => false,
                     },
                 );
@@ -1229,15 +1812,22 @@ impl<'t, 'e> Parser<'t, 'e> {
                 Restrict::LifeBound{ lt, bound }
             },
             _ => {
+                let for_lts = self.eat_opt_for_lts().unwrap_or_default();
                 let ty = self.eat_ty(true);
                 let bound = match_eat!{ self.tts;
-                    sym!(":") => self.eat_ty(true),
+                    sym!(":") => self.eat_bounds(),
+                    sym!("=", loc) => {
+                        self.eat_ty(true);
+                        let loc = str_range(self.source, loc, self.prev_pos());
+                        self.err(loc, "Equality constraints are not supported");
+                        vec![]
+                    },
                     _ => {
                         self.err_prev("Expect trait bounds");
-                        Ty::Error
+                        vec![]
                     },
                 };
-                Restrict::TraitBound{ ty, bound }
+                Restrict::TraitBound{ for_lts, ty, bound }
             },
         }
     }
@@ -1251,14 +1841,21 @@ impl<'t, 'e> Parser<'t, 'e> {
             ident!("_") => Pat::Hole,
             lit!(lit) => match_eat!{ self.tts;
                 sym!("..."), lit!(lit2) => Pat::Range(lit, lit2),
+                sym!("..="), lit!(lit2) => Pat::Range(lit, lit2),
                 _ => Pat::Literal(lit),
             },
-            sym!("&") =>
-                Pat::Ref(Box::new(self.eat_pat())),
+            sym!("&") => {
+                let is_mut = eatKw!(self.tts; "mut");
+                Pat::Ref{ is_mut, pat: Box::new(self.eat_pat()) }
+            },
+            sym!("&&", loc) => { // `&&` is lexed as one symbol
+                symBack!(self.tts, "&", loc);
+                Pat::Ref{ is_mut: false, pat: Box::new(self.eat_pat()) }
+            },
             tree!(loc, delim: Paren, tts) => {
                 let (mut v, tail) = self.new_inner(loc, tts)
                                         .eat_many_comma_tail_end(
-                    Parser::eat_pat,
+                    Parser::eat_pat_alt,
                 );
                 if v.len() == 1 && !tail {
                     Pat::Paren(Box::new(v.pop().unwrap()))
@@ -1283,6 +1880,23 @@ impl<'t, 'e> Parser<'t, 'e> {
         }
     }
 
+    /// Eat a pattern maybe with alternatives `<pat> | <pat> ..`, which is
+    /// allowed inside other patterns.
+    fn eat_pat_alt(&mut self) -> Pat<'t> {
+        let mut pats = vec![self.eat_pat()];
+        loop {
+            match_eat!{ self.tts;
+                sym!("|") => pats.push(self.eat_pat()),
+                _ => break,
+            }
+        }
+        if pats.len() == 1 {
+            pats.pop().unwrap()
+        } else {
+            Pat::Or(pats)
+        }
+    }
+
     /// Eat a pattern starting with an identifier,
     fn eat_pat_pathy(&mut self) -> Pat<'t> {
         let name = self.eat_path();
@@ -1290,7 +1904,7 @@ impl<'t, 'e> Parser<'t, 'e> {
             tree!(loc, delim: Paren, tts) => {
                 let (v, _) = self.new_inner(loc, tts)
                                  .eat_many_comma_tail_end(
-                    Parser::eat_pat,
+                    Parser::eat_pat_alt,
                 );
                 Pat::DestructTuple{ name, elems: v }
             },
@@ -1306,8 +1920,7 @@ impl<'t, 'e> Parser<'t, 'e> {
                 );
                 Pat::DestructNormal{ name, fields: v, ellipsis }
             },
-            _ => if let (false, 1, Some(&PathComp::Name{ name, hint: None })) =
-                    (name.is_absolute, name.comps.len(), name.comps.first()) {
+            _ => if let Some(name) = name.as_ident() {
                 let pat = match_eat!{ self.tts;
                     sym!("@") => Some(Box::new(self.eat_pat())),
                     _ => None,
@@ -1325,7 +1938,7 @@ impl<'t, 'e> Parser<'t, 'e> {
         let is_mut = eatKw!(self.tts; "mut");
         let name = self.eat_ident();
         let pat = match_eat!{ self.tts;
-            sym!(":") => Some(Box::new(self.eat_pat())),
+            sym!(":") => Some(Box::new(self.eat_pat_alt())),
             _ => None,
         };
         DestructField{ is_ref, is_mut, name, pat }
@@ -1334,9 +1947,36 @@ impl<'t, 'e> Parser<'t, 'e> {
     /// Eat and return a type. If `accect_traits`, it can accept
     /// `Tr1 + Tr2 + ..`.
     fn eat_ty(&mut self, accept_traits: bool) -> Ty<'t> {
+        if let Some(p) = self.eat_opt_plugin_invoke() {
+            return Ty::PluginInvoke(Box::new(p));
+        }
+        if self.is_for_func_ty() {
+            let for_lts = self.eat_opt_for_lts().unwrap_or_default();
+            let mut ty = self.eat_ty(false);
+            if let Ty::Func(ref mut fun_ty) = ty {
+                fun_ty.for_lts = for_lts;
+            }
+            return ty;
+        }
+        if let (Some(&kw!("Self")), next) = (self.tts.peek(0), self.tts.peek(1)) {
+            if !matches!(next, Some(&sym!("::"))) { // not `Self::Item`
+                self.tts.next();
+                return Ty::Self_;
+            }
+        }
+        if self.is_contextual_ty_kw("dyn") ||
+           matches!(self.tts.peek(0), Some(&kw!("dyn"))) {
+            self.tts.next(); // `dyn`
+            let (bounds, lt) = self.eat_ty_bounds(accept_traits);
+            return Ty::Dyn{ bounds, lt };
+        }
         match_eat!{ self.tts;
             ident!("_") => Ty::Hole,
             sym!("!") => Ty::Never,
+            kw!("impl") => {
+                let (bounds, lt) = self.eat_ty_bounds(accept_traits);
+                Ty::Impl{ bounds, lt }
+            },
             tree!(loc, delim: Paren, tts) => {
                 let (mut v, tail) = self.new_inner(loc, tts)
                                         .eat_many_comma_tail_end(
@@ -1372,6 +2012,11 @@ impl<'t, 'e> Parser<'t, 'e> {
                 let ty = Box::new(self.eat_ty(false));
                 Ty::Ref{ lt, is_mut, ty }
             },
+            sym!("&&", loc) => { // `&&` is lexed as one symbol
+                symBack!(self.tts, "&", loc);
+                let ty = Box::new(self.eat_ty(false));
+                Ty::Ref{ lt: None, is_mut: false, ty }
+            },
             sym!("*"), kw!("const") =>
                 Ty::Ptr{ is_mut: false, ty: Box::new(self.eat_ty(false)) },
             sym!("*"), kw!("mut") =>
@@ -1391,14 +2036,14 @@ impl<'t, 'e> Parser<'t, 'e> {
             _ => if accept_traits {
                 let (mut v, tail) = self.eat_many_sep_tail(
                     symbol_type!("+"),
-                    Parser::eat_ty_apply,
+                    Parser::eat_poly_trait,
                     |p| match p.tts.peek(0) {
                         Some(&sym!("+")) => false,
-                        _ => !p.is_ty_apply_begin()
+                        _ => !p.is_poly_trait_begin()
                     },
                 );
-                if v.len() == 1 && !tail {
-                    Ty::Apply(Box::new(v.pop().unwrap()))
+                if v.len() == 1 && !tail && v[0].is_plain() {
+                    Ty::Apply(Box::new(v.pop().unwrap().tr))
                 } else {
                     Ty::Traits(v)
                 }
@@ -1410,10 +2055,127 @@ impl<'t, 'e> Parser<'t, 'e> {
         }
     }
 
+    /// Return whether the next TTs are `for<'a, ..>` followed by a function
+    /// type, rather than a trait.
+    fn is_for_func_ty(&self) -> bool {
+        match (self.tts.peek(0), self.tts.peek(1)) {
+            (Some(&kw!("for")), Some(&sym!("<"))) => (),
+            _ => return false,
+        }
+        let mut i = 2;
+        loop {
+            match self.tts.peek(i) {
+                Some(&lt!(_)) | Some(&sym!(",")) => i += 1,
+                Some(&sym!(">")) => break,
+                _ => return false,
+            }
+        }
+        matches!(
+            self.tts.peek(i + 1),
+            Some(&kw!("fn")) | Some(&kw!("unsafe")) | Some(&kw!("extern"))
+        )
+    }
+
+    /// Return whether the next TT is the contextual keyword `kw` (like `dyn`)
+    /// followed by trait bounds, rather than a type named `kw`.
+    fn is_contextual_ty_kw(&self, kw: &str) -> bool {
+        match (self.tts.peek(0), self.tts.peek(1)) {
+            (Some(&ident!(s, loc)), Some(&ident!(_))) |
+            (Some(&ident!(s, loc)), Some(&kw!("Self"))) |
+            (Some(&ident!(s, loc)), Some(&lt!(_))) |
+            (Some(&ident!(s, loc)), Some(&kw!("for"))) |
+            (Some(&ident!(s, loc)), Some(&sym!("?"))) => s == kw && loc == kw,
+            _ => false,
+        }
+    }
+
+    /// Eat and return trait bounds `Tr1 + Tr2 + 'a + ..` with at most one
+    /// lifetime. A trailing `+` is allowed. If not `accept_plus`, only one
+    /// bound is eaten.
+    fn eat_ty_bounds(
+        &mut self,
+        accept_plus: bool,
+    ) -> (Vec<PolyTrait<'t>>, Option<Lifetime<'t>>) {
+        let mut bounds = vec![];
+        let mut lt = None;
+        loop {
+            match_eat!{ self.tts;
+                lt!(x, loc) => if lt.is_none() {
+                    lt = Some(x);
+                } else {
+                    self.err(loc, "Expect at most one lifetime bound");
+                },
+                _ => if self.is_poly_trait_begin() {
+                    bounds.push(self.eat_poly_trait());
+                } else {
+                    self.err_prev("Expect a trait or lifetime bound");
+                    break;
+                },
+            }
+            if !accept_plus {
+                break;
+            }
+            match_eat!{ self.tts;
+                sym!("+") => (),
+                _ => break,
+            }
+            match self.tts.peek(0) {
+                Some(&lt!(_)) => (),
+                _ if self.is_poly_trait_begin() => (),
+                _ => break, // trailing `+`
+            }
+        }
+        (bounds, lt)
+    }
+
+    /// Return whether the next TT can be the begin of PolyTrait.
+    fn is_poly_trait_begin(&self) -> bool {
+        match (self.tts.peek(0), self.tts.peek(1)) {
+            (Some(&sym!("?")), _) |
+            (Some(&kw!("for")), Some(&sym!("<"))) => true,
+            _ => self.is_ty_apply_begin(),
+        }
+    }
+
+    /// Eat and return a trait maybe with `?` or higher-ranked lifetimes, like
+    /// `?Sized`, `for<'a> Fn(&'a str)`.
+    fn eat_poly_trait(&mut self) -> PolyTrait<'t> {
+        let is_maybe = match_eat!{ self.tts;
+            sym!("?") => true,
+            _ => false,
+        };
+        let for_lts = self.eat_opt_for_lts().unwrap_or_default();
+        let tr = self.eat_ty_apply();
+        PolyTrait{ is_maybe, for_lts, tr }
+    }
+
+    /// Eat and return lifetimes in `for<'a, 'b, ..>`, or return None.
+    fn eat_opt_for_lts(&mut self) -> Option<Vec<Lifetime<'t>>> {
+        match_eat!{ self.tts;
+            kw!("for"), sym!("<") => {
+                let (v, _) = self.eat_many_sep_tail(
+                    symbol_type!(","),
+                    |p| match_eat!{ p.tts;
+                        lt!(x) => Some(x),
+                        _ => {
+                            p.err_prev("Expect a lifetime");
+                            None
+                        },
+                    },
+                    |p| p.try_eat_templ_end(),
+                );
+                Some(v.into_iter().flatten().collect())
+            },
+            _ => None,
+        }
+    }
+
     /// Return whether the next TT can be the begin of TyApply.
     fn is_ty_apply_begin(&self) -> bool {
         match self.tts.peek(0) {
+            Some(&sym!("<")) | Some(&sym!("<<")) | // qualified path
             Some(&sym!("::")) |
+            Some(&kw!("crate")) | Some(&kw!("super")) | Some(&kw!("self")) |
             Some(&ident!(_)) |
             Some(&kw!("Self")) => true,
             _ => false,
@@ -1425,9 +2187,22 @@ impl<'t, 'e> Parser<'t, 'e> {
     fn eat_opt_angle_ty_apply_args(&mut self) -> Option<Vec<TyApplyArg<'t>>> {
         match_eat!{ self.tts;
             sym!("<") => {
+                // Lifetimes go first, then types, then associated types.
+                let max_order = Cell::new(0);
                 let (args, _) = self.eat_many_sep_tail(
                     symbol_type!(","),
-                    Parser::eat_ty_apply_arg,
+                    |p| {
+                        let begin = p.next_pos();
+                        let arg = p.eat_ty_apply_arg();
+                        let order = match arg {
+                            TyApplyArg::Lifetime(_)   => 0,
+                            TyApplyArg::Ty(_) |
+                            TyApplyArg::Const(_)      => 1,
+                            TyApplyArg::AssocTy{ .. } => 2,
+                        };
+                        p.check_generic_arg_order(&max_order, order, begin);
+                        arg
+                    },
                     |p| p.try_eat_templ_end(),
                 );
                 Some(args)
@@ -1437,6 +2212,26 @@ impl<'t, 'e> Parser<'t, 'e> {
     }
 
 
+    /// Report the generic argument from `begin` to the previous TT if its
+    /// `order` (0 for lifetimes, 1 for types and 2 for associated types) is
+    /// less than `max_order` of previous ones. Then update `max_order`.
+    fn check_generic_arg_order(
+        &mut self,
+        max_order: &Cell<u8>,
+        order:     u8,
+        begin:     LocStr<'t>,
+    ) {
+        if order < max_order.get() {
+            let loc = str_range(self.source, begin, self.prev_pos());
+            self.err(loc, if order == 0 {
+                "Expect lifetime arguments before others"
+            } else {
+                "Expect type arguments before associated types"
+            });
+        }
+        max_order.set(max_order.get().max(order));
+    }
+
     fn eat_ty_apply(&mut self) -> TyApply<'t> {
         let name = self.eat_path();
         match_eat!{ self.tts;
@@ -1445,7 +2240,12 @@ impl<'t, 'e> Parser<'t, 'e> {
                                     .eat_many_comma_tail_end(
                     |p| p.eat_ty(true),
                 );
-                let ret_ty = self.eat_opt_ret_ty();
+                // `+` after it belongs to the outer bounds, like
+                // `T: Fn() -> u8 + Send`.
+                let ret_ty = match_eat!{ self.tts;
+                    sym!("->") => Some(Box::new(self.eat_ty(false))),
+                    _ => None,
+                };
                 TyApply::Paren{ name, args, ret_ty }
             },
             _ => {
@@ -1465,11 +2265,30 @@ impl<'t, 'e> Parser<'t, 'e> {
                 let ty = self.eat_ty(true);
                 TyApplyArg::AssocTy{ name: Err(&loc[..0]), ty } // before `=`
             },
-            ident!(name), sym!("=") => {
+            ident!(_, name), sym!("=") => {
                 let ty = self.eat_ty(true);
                 TyApplyArg::AssocTy{ name: Ok(name), ty }
             },
-            _ => TyApplyArg::Ty(self.eat_ty(true)),
+            _ => match self.eat_opt_const_arg() {
+                Some(e) => TyApplyArg::Const(e),
+                None    => TyApplyArg::Ty(self.eat_ty(true)),
+            },
+        }
+    }
+
+    /// Eat and return a const generic argument, which is a literal, a negative
+    /// literal or a block, or return None.
+    fn eat_opt_const_arg(&mut self) -> Option<Expr<'t>> {
+        match_eat!{ self.tts;
+            lit!(lit) => Some(Expr::Literal(lit)),
+            sym!("-", op_loc), lit!(lit) => Some(Expr::UnaryOp{
+                op: UnaryOp::Neg,
+                op_loc,
+                expr: Box::new(Expr::Literal(lit)),
+            }),
+            tree!(loc, delim: Brace, tts) =>
+                Some(self.new_inner(loc, tts).eat_block_expr_inner_end()),
+            _ => None,
         }
     }
 
@@ -1479,7 +2298,7 @@ impl<'t, 'e> Parser<'t, 'e> {
             tree!(loc, delim: Paren, tts) => {
                 self.new_inner(loc, tts).eat_many_comma_tail_last(
                     |p| match_eat!{ p.tts;
-                        ident!(name), sym!(":") =>
+                        ident!(_, name), sym!(":") =>
                             FuncTyParam{ name: Some(Ok(name))
                                        , ty: p.eat_ty(true) },
                         _ => FuncTyParam{ name: None
@@ -1499,6 +2318,7 @@ impl<'t, 'e> Parser<'t, 'e> {
         };
         let ret_ty = self.eat_opt_ret_ty();
         let fun_ty = FuncTy{
+            for_lts: vec![],
             is_unsafe,
             abi,
             args,
@@ -1523,7 +2343,9 @@ impl<'t, 'e> Parser<'t, 'e> {
     /// item-like-expr, it will return immediately without checking binary ops.
     /// Eg. `m!{} - 1` will be parsed into `m!{}` and `-1` will be remained.
     /// If `struct_expr` is false, it will not recognize struct expression
-    /// `S [<T, ..>] { .. }`。
+    /// `S [<T, ..>] { .. }`. It is used for the condition of `if`/`while`,
+    /// the iterator of `for` and the operand of `match`, where `{` starts a
+    /// block, unless the struct expression is inside parentheses.
     /// Reference:
     /// http://doc.rust-lang.org/reference/expressions.html#operator-precedence
     fn eat_expr(
@@ -1531,7 +2353,97 @@ impl<'t, 'e> Parser<'t, 'e> {
         item_like_first: bool,
         struct_expr: bool,
     ) -> Expr<'t> {
-        self.eat_expr_binop(item_like_first, struct_expr)
+        self.eat_expr_assign(item_like_first, struct_expr)
+    }
+
+    /// Eat the expression maybe with right-associative assignment operators.
+    /// See also: `Parser::eat_expr()`
+    fn eat_expr_assign(
+        &mut self,
+        item_like_first: bool,
+        struct_expr: bool,
+    ) -> Expr<'t> {
+        let l = self.eat_expr_place(item_like_first, struct_expr);
+        if item_like_first && l.is_item_like() {
+            return l;
+        }
+        let (op, op_loc) = match_eat!{ self.tts;
+            sym!("=", loc) => (BinaryOp::Assign, loc),
+            sym!("+=", loc) => (BinaryOp::AddAssign, loc),
+            sym!("-=", loc) => (BinaryOp::SubAssign, loc),
+            sym!("*=", loc) => (BinaryOp::MulAssign, loc),
+            sym!("/=", loc) => (BinaryOp::DivAssign, loc),
+            sym!("%=", loc) => (BinaryOp::ModAssign, loc),
+            sym!("&=", loc) => (BinaryOp::AndAssign, loc),
+            sym!("|=", loc) => (BinaryOp::OrAssign, loc),
+            sym!("^=", loc) => (BinaryOp::XorAssign, loc),
+            sym!("<<=", loc) => (BinaryOp::ShlAssign, loc),
+            sym!(">>=", loc) => (BinaryOp::ShrAssign, loc),
+            _ => return l,
+        };
+        let r = Box::new(self.eat_expr_assign(false, struct_expr));
+        Expr::BinaryOp{ op, op_loc, l: Box::new(l), r }
+    }
+
+    /// Eat the expression maybe with right-associative `<-`.
+    /// See also: `Parser::eat_expr()`
+    fn eat_expr_place(
+        &mut self,
+        item_like_first: bool,
+        struct_expr: bool,
+    ) -> Expr<'t> {
+        let l = self.eat_expr_range(item_like_first, struct_expr);
+        if item_like_first && l.is_item_like() {
+            return l;
+        }
+        match_eat!{ self.tts;
+            sym!("<-", op_loc) => Expr::BinaryOp{
+                op: BinaryOp::Place,
+                op_loc,
+                l: Box::new(l),
+                r: Box::new(self.eat_expr_place(false, struct_expr)),
+            },
+            _ => l,
+        }
+    }
+
+    /// Eat the expression maybe as the range `[<start>] (..|..=) [<end>]`.
+    /// See also: `Parser::eat_expr()`
+    fn eat_expr_range(
+        &mut self,
+        item_like_first: bool,
+        struct_expr: bool,
+    ) -> Expr<'t> {
+        let start = match self.tts.peek(0) {
+            Some(&sym!("..")) | Some(&sym!("..=")) | Some(&sym!("...")) =>
+                None,
+            _ => {
+                let e = self.eat_expr_binop(item_like_first, struct_expr);
+                if item_like_first && e.is_item_like() {
+                    return e;
+                }
+                Some(Box::new(e))
+            },
+        };
+        let (is_inclusive, op_loc) = match_eat!{ self.tts;
+            sym!("..", loc) => (false, loc),
+            sym!("..=", loc) => (true, loc),
+            sym!("...", loc) => (true, loc),
+            _ => return *start.unwrap(), // not a range
+        };
+        let has_end = match self.tts.peek(0) {
+            Some(&tree!(_, delim: Brace, ..)) => struct_expr, // `for _ in 0.. {}`
+            _ => self.is_expr_begin(),
+        };
+        let end = if has_end {
+            Some(Box::new(self.eat_expr_binop(false, struct_expr)))
+        } else {
+            if is_inclusive {
+                self.err(op_loc, "Expect the end of an inclusive range");
+            }
+            None
+        };
+        Expr::Range{ start, end, is_inclusive, op_loc }
     }
 
     /// Eat the cast expression maybe with binary operators.
@@ -1595,12 +2507,6 @@ impl<'t, 'e> Parser<'t, 'e> {
             ($efst:expr; $($t:tt)*) => { m!([] 0; $($t)* @ $efst) };
         }
         m!{ efst;
-            R: "="(Assign), "+="(AddAssign), "-="(SubAssign),
-                "*="(MulAssign), "/="(DivAssign), "%="(ModAssign),
-                "&="(AndAssign), "|="(OrAssign),
-                "<<="(ShlAssign), ">>="(ShrAssign);
-            R: "<-"(Place);
-            L: ".."(Range), "..."(RangeInclusive);
             L: "||"(LogOr);
             L: "&&"(LogAnd);
             L: "=="(Equ), "!="(Ne),
@@ -1610,7 +2516,7 @@ impl<'t, 'e> Parser<'t, 'e> {
             L: "&"(And);
             L: "<<"(Shl), ">>"(Shr);
             L: "+"(Add), "-"(Sub);
-            L: "*"(Mul), "/"(Div);
+            L: "*"(Mul), "/"(Div), "%"(Mod);
         }
     }
 
@@ -1654,6 +2560,10 @@ impl<'t, 'e> Parser<'t, 'e> {
             sym!("!", loc) => Some((UnaryOp::Not, loc)),
             sym!("&", loc), kw!("mut") => Some((UnaryOp::BorrowMut, loc)),
             sym!("&", loc) => Some((UnaryOp::Borrow, loc)),
+            sym!("&&", loc) => { // `&&` is lexed as one symbol
+                symBack!(self.tts, "&", loc);
+                Some((UnaryOp::Borrow, &loc[..1]))
+            },
             sym!("*", loc) => Some((UnaryOp::Deref, loc)),
             _ => None,
         };
@@ -1668,6 +2578,36 @@ impl<'t, 'e> Parser<'t, 'e> {
     }
 
     /// Eat the expression maybe with postfix operators.
+    /// Return the tuple fields of `obj` from a float literal `loc` after `.`,
+    /// since `a.0.1` is lexed into `a`, `.` and `0.1`.
+    fn split_float_tuple_index(
+        &mut self,
+        obj: Expr<'t>,
+        loc: LocStr<'t>,
+    ) -> Expr<'t> {
+        let parse = |s: &str| if s.bytes().all(|c| c.is_ascii_digit()) {
+            s.parse::<umax>().ok()
+        } else {
+            None
+        };
+        let pos = loc.find('.').unwrap_or(0);
+        let (l, r) = (&loc[..pos], &loc[pos + 1..]);
+        match (parse(l), parse(r)) {
+            (Some(il), Some(ir)) => {
+                let obj = Expr::TupleField{
+                    obj: Box::new(obj),
+                    ind_loc: l,
+                    index: il,
+                };
+                Expr::TupleField{ obj: Box::new(obj), ind_loc: r, index: ir }
+            },
+            _ => {
+                self.err(loc, "Expect a tuple index");
+                obj
+            },
+        }
+    }
+
     /// See also: `Parser::eat_expr()`
     fn eat_expr_postfix(
         &mut self,
@@ -1688,6 +2628,10 @@ impl<'t, 'e> Parser<'t, 'e> {
                     },
                 sym!("."), lit_int!(index, ind_loc) =>
                     e = Expr::TupleField{ obj: Box::new(e), ind_loc, index },
+                sym!("."), lit!(Literal::FloatLike{ .. }, loc) =>
+                    e = self.split_float_tuple_index(e, loc),
+                sym!("."), kw!("await") => // a field in Rust 2015
+                    e = Expr::Await(Box::new(e)),
                 sym!(".") => {
                     let comp = self.eat_path_comp();
                     match_eat!{ self.tts;
@@ -1756,6 +2700,13 @@ impl<'t, 'e> Parser<'t, 'e> {
         if let Some(p) = self.eat_opt_plugin_invoke() {
             return Expr::PluginInvoke(p);
         }
+        if let (Some(&sym!(".", l)), Some(&lit!(Literal::IntLike{ .. }, r))) =
+                (self.tts.peek(0), self.tts.peek(1)) { // `.5`
+            self.tts.next();
+            self.tts.next();
+            self.err(str_range(self.source, l, r), "Expect digits before `.`");
+            return Expr::Error;
+        }
         match_eat!{ self.tts;
             lit!(lit) => Expr::Literal(lit),
             tree!(loc, delim: Paren, tts) => {
@@ -1775,14 +2726,27 @@ impl<'t, 'e> Parser<'t, 'e> {
                 self.new_inner(loc, tts).eat_block_expr_inner_end(),
             kw!("unsafe") =>
                 Expr::Unsafe(Box::new(self.eat_block_expr())),
+            kw!("const") =>
+                Expr::ConstBlock(Box::new(self.eat_block_expr())),
+            // `async` is a contextual keyword in Rust 2015.
+            ckw!("async"), tree!(loc, delim: Brace, tts) =>
+                self.eat_async_block_tail(false, loc, tts),
+            ckw!("async"), kw!("move"), tree!(loc, delim: Brace, tts) =>
+                self.eat_async_block_tail(true, loc, tts),
+            kw!("async"), tree!(loc, delim: Brace, tts) =>
+                self.eat_async_block_tail(false, loc, tts),
+            kw!("async"), kw!("move"), tree!(loc, delim: Brace, tts) =>
+                self.eat_async_block_tail(true, loc, tts),
+            kw!("try") =>
+                Expr::TryBlock(Box::new(self.eat_block_expr())),
             sym!("|", loc) =>
                 self.eat_lambda_expr_tail(false, loc, false),
             sym!("||", loc) =>
-                self.eat_lambda_expr_tail(false, &loc[..1], true),
+                self.eat_lambda_expr_tail(false, loc, true),
             kw!("move"), sym!("|", loc) =>
                 self.eat_lambda_expr_tail(true, loc, false),
             kw!("move"), sym!("||", loc) =>
-                self.eat_lambda_expr_tail(true, &loc[..1], true),
+                self.eat_lambda_expr_tail(true, loc, true),
             kw!("break", kw_loc), lt!(lt) =>
                 Expr::Break{ label: Some(lt)
                            , kw_loc
@@ -1840,13 +2804,14 @@ impl<'t, 'e> Parser<'t, 'e> {
     fn eat_while_tail(&mut self, label: Option<Lifetime<'t>>) -> Expr<'t> {
         match_eat!{ self.tts;
             kw!("let") => {
-                let pat = Box::new(self.eat_pat());
+                let pat = Box::new(self.eat_pat_alt());
                 let expr = match_eat!{ self.tts;
                     sym!("=") => self.eat_expr(false, false),
                     _ => Expr::Error,
                 };
                 let body = Box::new(self.eat_block_expr());
                 Expr::WhileLet {
+                    label,
                     pat,
                     expr: Box::new(expr),
                     body,
@@ -1884,7 +2849,7 @@ impl<'t, 'e> Parser<'t, 'e> {
         };
         match_eat!{ self.tts;
             kw!("let") => {
-                let pat = Box::new(self.eat_pat());
+                let pat = Box::new(self.eat_pat_alt());
                 let match_expr = match_eat!{ self.tts;
                     sym!("=") => Box::new(self.eat_expr(false, false)),
                     _ => Box::new(Expr::Error),
@@ -1904,33 +2869,8 @@ impl<'t, 'e> Parser<'t, 'e> {
     fn eat_match_tail(&mut self, kw_loc: LocStr<'t>) -> Expr<'t> {
         let expr = Box::new(self.eat_expr(false, false));
         let arms = match_eat!{ self.tts;
-            tree!(loc, delim: Brace, tts) => {
-                let (arms, _) = self.new_inner(loc, tts)
-                                    .eat_many_comma_tail_end(
-                    |p| {
-                        let pats = p.eat_many_sep(
-                            symbol_type!("|"),
-                            "Expect a pattern",
-                            Parser::eat_pat,
-                            |p| match p.tts.peek(0) {
-                                Some(&sym!("|")) => false,
-                                _ => !p.is_pat_begin(),
-                            },
-                        );
-                        let cond = match_eat!{ p.tts;
-                            kw!("if") =>
-                                Some(Box::new(p.eat_expr(false, true))),
-                            _ => None,
-                        };
-                        let expr = match_eat!{ p.tts;
-                            sym!("=>") => p.eat_expr(false, true),
-                            _ => Expr::Error,
-                        };
-                        MatchArm{ pats, cond, expr: Box::new(expr) }
-                    },
-                );
-                arms
-            },
+            tree!(loc, delim: Brace, tts) =>
+                self.new_inner(loc, tts).eat_match_arms_end(),
             _ => {
                 self.err_prev("Expect the body in `{}`");
                 vec![]
@@ -1939,6 +2879,68 @@ impl<'t, 'e> Parser<'t, 'e> {
         Expr::Match{ kw_loc, expr, arms }
     }
 
+    /// Eat match arms to the end. The comma after an arm can be omitted
+    /// if it is the last one or its body is block-like.
+    fn eat_match_arms_end(mut self) -> Vec<MatchArm<'t>> {
+        let mut arms = vec![];
+        while !self.is_end() {
+            let arm = self.eat_match_arm();
+            let comma_opt = arm.expr.is_item_like();
+            arms.push(arm);
+            if comma_opt && !matches!(self.tts.peek(0), Some(&sym!(","))) {
+                continue;
+            }
+            while !self.is_end() { // until eating a `,` or reach the end
+                match_eat!{ self.tts;
+                    sym!(",") => break,
+                    (_, loc) => self.err(loc, "Expect `,`"),
+                    _ => unreachable!(), // not end
+                }
+            }
+        }
+        arms
+    }
+
+    /// Eat a match arm `|? <pat> (| <pat>)* (if <expr>)? => <expr>`.
+    fn eat_match_arm(&mut self) -> MatchArm<'t> {
+        match_eat!{ self.tts;
+            sym!("|") => (),
+            _ => (),
+        }
+        let pats = self.eat_many_sep(
+            symbol_type!("|"),
+            "Expect a pattern",
+            Parser::eat_pat,
+            |p| match p.tts.peek(0) {
+                Some(&sym!("|")) => false,
+                _ => !p.is_pat_begin(),
+            },
+        );
+        let cond = match_eat!{ self.tts;
+            kw!("if") => Some(Box::new(self.eat_expr(false, true))),
+            _ => None,
+        };
+        let expr = match_eat!{ self.tts;
+            sym!("=>") => self.eat_expr(false, true),
+            _ => {
+                self.err_prev("Expect `=> <expr>`");
+                Expr::Error
+            },
+        };
+        MatchArm{ pats, cond, expr: Box::new(expr) }
+    }
+
+    /// Return the async block with the block `tts` in `loc`.
+    fn eat_async_block_tail(
+        &mut self,
+        is_move: bool,
+        loc:     LocStr<'t>,
+        tts:     Vec<TT<'t>>,
+    ) -> Expr<'t> {
+        let block = self.new_inner(loc, tts).eat_block_expr_inner_end();
+        Expr::Async{ is_move, block: Box::new(block) }
+    }
+
     /// Eat the inner of a block expression to the end, and return the block
     /// expression.
     fn eat_block_expr_inner_end(mut self) -> Expr<'t> {
@@ -1951,13 +2953,15 @@ impl<'t, 'e> Parser<'t, 'e> {
         let mut ret: Option<Expr> = None;
         while !self.is_end() {
             if let Some(expr) = ret.take() { // .. <expr> |;
-                if !expr.is_item_like() {
-                    self.expect_semi();
+                let is_item_like = expr.is_item_like();
+                let has_semi = eat_semi(&mut self);
+                if !has_semi && !is_item_like {
+                    self.err_prev("Expect a semicolon");
                 }
                 let stmt = match expr {
-                    Expr::PluginInvoke(p) =>
-                        Stmt::PluginInvoke(p),
-                    _ => Stmt::Expr(expr),
+                    Expr::PluginInvoke(p) => Stmt::PluginInvoke(p),
+                    _ if is_item_like && !has_semi => Stmt::Expr(expr),
+                    _ => Stmt::Semi(expr),
                 };
                 stmts.push(stmt);
             } else if eat_semi(&mut self) { // .. <expr>; |;
@@ -1966,23 +2970,31 @@ impl<'t, 'e> Parser<'t, 'e> {
                 if let Some(item) = self.eat_item() {
                     stmts.push(Stmt::Item(Box::new(item)));
                 }
+            } else if let Some(&sym!("#")) |
+                          Some(&tok!(Tokk::OuterDoc(_))) = self.tts.peek(0) {
+                let begin = self.next_pos();
+                let attrs = self.eat_outer_attrs();
+                if self.is_item_begin() {
+                    if let Some(item) = self.eat_item_after_attrs(begin, attrs) {
+                        stmts.push(Stmt::Item(Box::new(item)));
+                    }
+                } else {
+                    let loc = str_range(self.source, begin, self.prev_pos());
+                    self.err(loc, "Expect an item after attributes");
+                }
             } else { match_eat!{ self.tts;
                 kw!("let") => {
                     let pat = self.eat_pat();
                     let ty = match_eat!{ self.tts;
-                        sym!(":") => self.eat_ty(true),
-                        _ => Ty::Error,
+                        sym!(":") => Some(Box::new(self.eat_ty(true))),
+                        _ => None,
                     };
-                    let expr = match_eat!{ self.tts;
-                        sym!("=") => self.eat_expr(false, true),
-                        _ => Expr::Error,
+                    let init = match_eat!{ self.tts;
+                        sym!("=") => Some(Box::new(self.eat_expr(false, true))),
+                        _ => None,
                     };
                     self.expect_semi();
-                    stmts.push(Stmt::Let {
-                        pat,
-                        ty: Box::new(ty),
-                        expr: Box::new(expr),
-                    });
+                    stmts.push(Stmt::Let{ pat, ty, init });
                 },
                 _ => if self.is_expr_begin() {
                     ret = Some(self.eat_expr(true, true));
@@ -2020,14 +3032,19 @@ impl<'t, 'e> Parser<'t, 'e> {
         }
     }
 
-    /// If the next TT starts with `|`, eat `|` and return true. Otherwise,
-    /// return false.
+    /// If the next TT starts with `|`, eat `|` and return true. At the end,
+    /// report the missing `|` and return true. Otherwise, return false.
     fn try_eat_lambda_arg_end(&mut self) -> bool {
         match_eat!{ self.tts;
             sym!("|") => true,
             sym!("||", loc) => { symBack!(self.tts, "|", loc); true },
             sym!("|=", loc) => { symBack!(self.tts, "=", loc); true },
-            _ => false,
+            _ => if self.is_end() {
+                self.err_prev("Expect `|`");
+                true
+            } else {
+                false
+            },
         }
     }
 
@@ -2038,15 +3055,15 @@ impl<'t, 'e> Parser<'t, 'e> {
         loc:       LocStr<'t>,
         is_closed: bool,
     ) -> Expr<'t> {
-        let args = if is_closed {
-            vec![]
+        let (loc, args) = if is_closed {
+            (loc, vec![])
         } else {
             let (v, _) = self.eat_many_sep_tail(
                 symbol_type!(","),
                 Parser::eat_lambda_param,
                 Parser::try_eat_lambda_arg_end,
             );
-            v
+            (str_range(self.source, loc, self.prev_pos()), v)
         };
         let (ret_ty, body) = match_eat!{ self.tts;
             sym!("->") => (
@@ -2078,13 +3095,25 @@ impl<'t, 'e> Parser<'t, 'e> {
         &mut self,
     ) -> (Vec<ExprStructField<'t>>, Option<Box<Expr<'t>>>) {
         let (v, base) = self.eat_many_comma_tail_last(
-            |p| {
-                let name = p.eat_ident();
-                let expr = match_eat!{ p.tts;
-                    sym!(":") => Some(Box::new(p.eat_expr(false, true))),
-                    _ => None,
-                };
-                ExprStructField{ name, expr }
+            |p| match_eat!{ p.tts;
+                lit_int!(_, loc) => { // `0: <expr>` for tuple structs
+                    let expr = match_eat!{ p.tts;
+                        sym!(":") => p.eat_expr(false, true),
+                        _ => {
+                            p.err_prev("Expect `: <expr>`");
+                            Expr::Error
+                        },
+                    };
+                    ExprStructField{ name: Ok(loc), expr: Some(Box::new(expr)) }
+                },
+                _ => {
+                    let name = p.eat_ident();
+                    let expr = match_eat!{ p.tts;
+                        sym!(":") => Some(Box::new(p.eat_expr(false, true))),
+                        _ => None,
+                    };
+                    ExprStructField{ name, expr }
+                },
             },
             |p| match_eat!{ p.tts;
                 sym!("..") => Some(Box::new(p.eat_expr(false, true))),
@@ -2097,22 +3126,44 @@ impl<'t, 'e> Parser<'t, 'e> {
 
     /// Eat and return an plugin invoke, or return None.
     fn eat_opt_plugin_invoke(&mut self) -> Option<PluginInvoke<'t>> {
+        if !self.is_plugin_invoke_begin() {
+            return None;
+        }
+        let name = self.eat_path();
         match_eat!{ self.tts;
-            ident!(name), sym!("!") => {
-                let ident = match_eat!{ self.tts;
-                    ident!(s) => Some(Ok(s)),
-                    _ => None,
-                };
-                match_eat!{ self.tts;
-                    tt@tree!(_, ..) =>
-                        Some(PluginInvoke{ name: Ok(name), ident, tt }),
-                    _ => {
-                        self.err_prev("Expect token tree (`()`, `[]`, `{}`)");
-                        None
-                    },
-                }
-            },
+            sym!("!") => (),
+            _ => unreachable!(), // checked by `is_plugin_invoke_begin()`
+        }
+        let ident = match_eat!{ self.tts;
+            ident!(_, s) => Some(Ok(s)),
             _ => None,
+        };
+        match_eat!{ self.tts;
+            tt@tree!(_, ..) =>
+                Some(PluginInvoke{ name, ident, tt }),
+            _ => {
+                self.err_prev("Expect token tree (`()`, `[]`, `{}`)");
+                None
+            },
+        }
+    }
+
+    /// Return whether the next TTs are `[::] <ident> [:: <ident>]... !`.
+    fn is_plugin_invoke_begin(&self) -> bool {
+        let mut i = 0;
+        if let Some(&sym!("::")) = self.tts.peek(i) {
+            i += 1;
+        }
+        loop {
+            match self.tts.peek(i) {
+                Some(&ident!(_)) => i += 1,
+                _ => return false,
+            }
+            match self.tts.peek(i) {
+                Some(&sym!("::")) => i += 1,
+                Some(&sym!("!")) => return true,
+                _ => return false,
+            }
         }
     }
 }
@@ -0,0 +1,1436 @@
+//! Print AST nodes back to Rust source.
+//!
+//! Types, patterns and other single-line nodes implement `Display`. Items,
+//! statements and expressions are printed by a `Printer` with configurable
+//! indentation, which also backs their `Display` implementations. Printing a
+//! parsed module and parsing it again gives the same AST, except for locations.
+//!
+//! # Example
+//!
+//! ```
+//! use mair::parse::ast::*;
+//! use mair::parse::print::Printer;
+//! let body = Expr::Block{ attrs: vec![], stmts: vec![], ret: Some(Box::new(
+//!     Expr::Literal(Literal::Bool(true)),
+//! )) };
+//! let mut p = Printer::new(2);
+//! p.print_expr(&Expr::Loop{ label: Some("a"), body: Box::new(body) });
+//! assert_eq!(p.finish(), "'a: loop {\n  true\n}");
+//! ```
+//!
+//! ```
+//! use mair::parse::ast::Ty;
+//! assert_eq!(format!("{}", Ty::Ref{
+//!     lt: Some("a"),
+//!     is_mut: true,
+//!     ty: Box::new(Ty::Slice(Box::new(Ty::from_name("u8")))),
+//! }), "&'a mut [u8]");
+//! ```
+use std::fmt::{Display, Formatter, Result, Write};
+use super::ast::*;
+use super::lexer::TokenKind;
+
+/// Write `xs` separated by `sep` with `f`.
+fn write_sep<T, F>(
+    fo:  &mut Formatter,
+    xs:  &[T],
+    sep: &str,
+    f:   F,
+) -> Result
+where F: Fn(&mut Formatter, &T) -> Result {
+    for (i, x) in xs.iter().enumerate() {
+        if i != 0 {
+            fo.write_str(sep)?;
+        }
+        f(fo, x)?;
+    }
+    Ok(())
+}
+
+fn write_ident(f: &mut Formatter, name: &Ident) -> Result {
+    match *name {
+        Ok(s) => f.write_str(s),
+        Err(_) => Ok(()), // missing
+    }
+}
+
+fn write_lt(f: &mut Formatter, lt: Lifetime) -> Result {
+    write!(f, "'{}", lt)
+}
+
+/// Write `for<'a, ..> ` if `lts` is not empty.
+fn write_for_lts(f: &mut Formatter, lts: &[Lifetime]) -> Result {
+    if !lts.is_empty() {
+        f.write_str("for<")?;
+        write_sep(f, lts, ", ", |f, &lt| write_lt(f, lt))?;
+        f.write_str("> ")?;
+    }
+    Ok(())
+}
+
+fn write_abi(f: &mut Formatter, abi: &ABI) -> Result {
+    match *abi {
+        ABI::Normal => Ok(()),
+        ABI::Extern => f.write_str("extern "),
+        ABI::Specific{ ref abi, .. } => write!(f, "extern {:?} ", abi),
+    }
+}
+
+fn write_bounds(
+    f:      &mut Formatter,
+    bounds: &[PolyTrait],
+    lt:     Option<Lifetime>,
+) -> Result {
+    write_sep(f, bounds, " + ", |f, tr| tr.fmt(f))?;
+    if let Some(lt) = lt {
+        if !bounds.is_empty() {
+            f.write_str(" + ")?;
+        }
+        write_lt(f, lt)?;
+    }
+    Ok(())
+}
+
+impl<'a> Display for Ty<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Ty::Error => Ok(()),
+            Ty::Hole => f.write_str("_"),
+            Ty::Never => f.write_str("!"),
+            Ty::Self_ => f.write_str("Self"),
+            Ty::Traits(ref bounds) => write_bounds(f, bounds, None),
+            Ty::Dyn{ ref bounds, lt } => {
+                f.write_str("dyn ")?;
+                write_bounds(f, bounds, lt)
+            },
+            Ty::Impl{ ref bounds, lt } => {
+                f.write_str("impl ")?;
+                write_bounds(f, bounds, lt)
+            },
+            Ty::Apply(ref app) => app.fmt(f),
+            Ty::Tuple(ref tys) => {
+                f.write_char('(')?;
+                write_sep(f, tys, ", ", |f, ty| ty.fmt(f))?;
+                if tys.len() == 1 {
+                    f.write_char(',')?;
+                }
+                f.write_char(')')
+            },
+            Ty::Paren(ref ty) => write!(f, "({})", ty),
+            Ty::Ref{ lt, is_mut, ref ty } => {
+                f.write_char('&')?;
+                if let Some(lt) = lt {
+                    write_lt(f, lt)?;
+                    f.write_char(' ')?;
+                }
+                if is_mut {
+                    f.write_str("mut ")?;
+                }
+                ty.fmt(f)
+            },
+            Ty::Ptr{ is_mut, ref ty } =>
+                write!(f, "*{} {}", if is_mut { "mut" } else { "const" }, ty),
+            Ty::Slice(ref ty) => write!(f, "[{}]", ty),
+            Ty::Array{ ref ty, ref size } => write!(f, "[{}; {}]", ty, size),
+            Ty::Func(ref fty) => fty.fmt(f),
+            Ty::PluginInvoke(ref p) => p.fmt(f),
+        }
+    }
+}
+
+impl<'a> Display for FuncTy<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write_for_lts(f, &self.for_lts)?;
+        if self.is_unsafe {
+            f.write_str("unsafe ")?;
+        }
+        write_abi(f, &self.abi)?;
+        f.write_str("fn(")?;
+        write_sep(f, &self.args, ", ", |f, arg| {
+            if let Some(ref name) = arg.name {
+                write_ident(f, name)?;
+                f.write_str(": ")?;
+            }
+            arg.ty.fmt(f)
+        })?;
+        if self.is_va {
+            if !self.args.is_empty() {
+                f.write_str(", ")?;
+            }
+            f.write_str("...")?;
+        }
+        f.write_char(')')?;
+        if let Some(ref ty) = self.ret_ty {
+            write!(f, " -> {}", ty)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for PolyTrait<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if self.is_maybe {
+            f.write_char('?')?;
+        }
+        write_for_lts(f, &self.for_lts)?;
+        self.tr.fmt(f)
+    }
+}
+
+impl<'a> Display for Bound<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Bound::Trait(ref tr) => tr.fmt(f),
+            Bound::Lifetime(lt)  => write_lt(f, lt),
+        }
+    }
+}
+
+/// `Tr1 + 'a + ..`
+struct Bounds<'b, 'a: 'b>(&'b [Bound<'a>]);
+
+impl<'b, 'a> Display for Bounds<'b, 'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write_sep(f, self.0, " + ", |f, b| b.fmt(f))
+    }
+}
+
+/// Write a const generic argument. A block with only a value is kept in one
+/// line, like `{ N + 1 }`.
+fn write_const_arg(f: &mut Formatter, e: &Expr) -> Result {
+    match *e {
+        Expr::Block{ ref attrs, ref stmts, ret: Some(ref ret) }
+            if attrs.is_empty() && stmts.is_empty() =>
+            write!(f, "{{ {} }}", ret),
+        _ => e.fmt(f),
+    }
+}
+
+impl<'a> Display for TyApply<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            TyApply::Angle{ ref name, ref args } => {
+                name.fmt(f)?;
+                if !args.is_empty() {
+                    f.write_char('<')?;
+                    write_sep(f, args, ", ", |f, arg| match *arg {
+                        TyApplyArg::Lifetime(lt) => write_lt(f, lt),
+                        TyApplyArg::Ty(ref ty) => ty.fmt(f),
+                        TyApplyArg::Const(ref e) => write_const_arg(f, e),
+                        TyApplyArg::AssocTy{ ref name, ref ty } => {
+                            write_ident(f, name)?;
+                            write!(f, " = {}", ty)
+                        },
+                    })?;
+                    f.write_char('>')?;
+                }
+                Ok(())
+            },
+            TyApply::Paren{ ref name, ref args, ref ret_ty } => {
+                write!(f, "{}(", name)?;
+                write_sep(f, args, ", ", |f, ty| ty.fmt(f))?;
+                f.write_char(')')?;
+                if let Some(ref ty) = *ret_ty {
+                    write!(f, " -> {}", ty)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl<'a> Display for Path<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if let Some(ref qself) = self.qself {
+            write!(f, "<{}", qself.ty)?;
+            if let Some(ref tr) = qself.tr {
+                write!(f, " as {}", tr)?;
+            }
+            f.write_str(">::")?;
+        }
+        self.root.fmt(f)?;
+        write_sep(f, &self.comps, "::", |f, comp| comp.fmt(f))
+    }
+}
+
+impl Display for PathRoot {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            PathRoot::None => Ok(()),
+            PathRoot::Global => f.write_str("::"),
+            PathRoot::Crate => f.write_str("crate::"),
+            PathRoot::Super(n) => {
+                for _ in 0..n {
+                    f.write_str("super::")?;
+                }
+                Ok(())
+            },
+            PathRoot::SelfMod => f.write_str("self::"),
+        }
+    }
+}
+
+impl<'a> Display for PathComp<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            PathComp::Self_(_) => f.write_str("self"),
+            PathComp::SelfTy_(_) => f.write_str("Self"),
+            PathComp::Super(_) => f.write_str("super"),
+            PathComp::Name{ ref name, ref hint } => {
+                write_ident(f, name)?;
+                if let Some(ref hint) = *hint {
+                    f.write_str("::<")?;
+                    write_sep(f, hint, ", ", |f, arg| match *arg {
+                        TyHintArg::Lifetime(lt) => write_lt(f, lt),
+                        TyHintArg::Ty(ref ty) => ty.fmt(f),
+                        TyHintArg::Const(ref e) => write_const_arg(f, e),
+                    })?;
+                    f.write_char('>')?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Write an escaped char in a char or string literal. Bytes out of ASCII are
+/// written as `\xNN`.
+fn write_escaped(f: &mut Formatter, ch: char, is_byte: bool) -> Result {
+    if is_byte && ch as u32 >= 0x80 {
+        write!(f, "\\x{:02x}", ch as u32)
+    } else {
+        write!(f, "{}", ch.escape_default())
+    }
+}
+
+impl<'a> Display for Literal<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Literal::CharLike{ is_byte, ch } => {
+                if is_byte {
+                    f.write_char('b')?;
+                }
+                f.write_char('\'')?;
+                write_escaped(f, ch, is_byte)?;
+                f.write_char('\'')
+            },
+            Literal::StrLike{ is_bytestr, ref s } => {
+                if is_bytestr {
+                    f.write_char('b')?;
+                }
+                f.write_char('"')?;
+                for ch in s.chars() {
+                    write_escaped(f, ch, is_bytestr)?;
+                }
+                f.write_char('"')
+            },
+            Literal::IntLike{ ref ty, val } => {
+                write!(f, "{}", val)?;
+                if let Some(ref ty) = *ty {
+                    ty.fmt(f)?;
+                }
+                Ok(())
+            },
+            Literal::FloatLike{ ref ty, val } => {
+                match *ty {
+                    Some(ref ty) => write!(f, "{}{}", val, ty)?,
+                    None => write!(f, "{:?}", val)?, // keep a `.`
+                }
+                Ok(())
+            },
+            Literal::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+impl<'a> Display for Pat<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Pat::Hole => f.write_char('_'),
+            Pat::BindLike{ ref name, is_ref, is_mut, ref pat } => {
+                if is_ref {
+                    f.write_str("ref ")?;
+                }
+                if is_mut {
+                    f.write_str("mut ")?;
+                }
+                write_ident(f, name)?;
+                if let Some(ref pat) = *pat {
+                    write!(f, " @ {}", pat)?;
+                }
+                Ok(())
+            },
+            Pat::Path(ref path) => path.fmt(f),
+            Pat::Literal(ref lit) => lit.fmt(f),
+            Pat::Range(ref l, ref r) => write!(f, "{}..={}", l, r),
+            Pat::Ref{ is_mut, ref pat } =>
+                write!(f, "&{}{}", if is_mut { "mut " } else { "" }, pat),
+            Pat::Tuple(ref pats) => {
+                f.write_char('(')?;
+                write_sep(f, pats, ", ", |f, pat| pat.fmt(f))?;
+                if pats.len() == 1 {
+                    f.write_char(',')?;
+                }
+                f.write_char(')')
+            },
+            Pat::Paren(ref pat) => write!(f, "({})", pat),
+            Pat::Or(ref pats) => write_sep(f, pats, " | ", |f, pat| pat.fmt(f)),
+            Pat::DestructTuple{ ref name, ref elems } => {
+                write!(f, "{}(", name)?;
+                write_sep(f, elems, ", ", |f, pat| pat.fmt(f))?;
+                f.write_char(')')
+            },
+            Pat::DestructNormal{ ref name, ref fields, ellipsis } => {
+                write!(f, "{} {{", name)?;
+                if fields.is_empty() && !ellipsis {
+                    return f.write_char('}');
+                }
+                f.write_char(' ')?;
+                write_sep(f, fields, ", ", |f, field| {
+                    if field.is_ref {
+                        f.write_str("ref ")?;
+                    }
+                    if field.is_mut {
+                        f.write_str("mut ")?;
+                    }
+                    write_ident(f, &field.name)?;
+                    if let Some(ref pat) = field.pat {
+                        write!(f, ": {}", pat)?;
+                    }
+                    Ok(())
+                })?;
+                if ellipsis {
+                    if !fields.is_empty() {
+                        f.write_str(", ")?;
+                    }
+                    f.write_str("..")?;
+                }
+                f.write_str(" }")
+            },
+            Pat::PluginInvoke(ref p) => p.fmt(f),
+        }
+    }
+}
+
+impl<'a> Display for PluginInvoke<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}!", self.name)?;
+        if let Some(ref ident) = self.ident {
+            f.write_char(' ')?;
+            write_ident(f, ident)?;
+            f.write_char(' ')?;
+        }
+        write_tt(f, &self.tt)
+    }
+}
+
+/// Reconstruct the source of a token tree. Tokens are separated by a space
+/// unless it is safe and more readable to join them, like `a::b`, `f(x)` or
+/// `$x`.
+pub fn write_tt<W: Write>(f: &mut W, tt: &TT) -> Result {
+    match *tt {
+        (TTKind::Token(_), loc) => f.write_str(loc),
+        (TTKind::Tree{ delim, ref tts }, _) => {
+            let (open, close) = match delim {
+                Delimiter::Paren => ('(', ')'),
+                Delimiter::Bracket => ('[', ']'),
+                Delimiter::Brace => ('{', '}'),
+            };
+            let pad = delim == Delimiter::Brace && !tts.is_empty();
+            f.write_char(open)?;
+            if pad {
+                f.write_char(' ')?;
+            }
+            for (i, tt) in tts.iter().enumerate() {
+                if i != 0 && need_space(&tts[i - 1].0, &tt.0, tts.get(i + 1)) {
+                    f.write_char(' ')?;
+                }
+                write_tt(f, tt)?;
+            }
+            if pad {
+                f.write_char(' ')?;
+            }
+            f.write_char(close)
+        },
+    }
+}
+
+/// Whether a space is needed between `prev` and `cur`, which is followed by
+/// `next`. Never join tokens which may be lexed differently, like `1` `.` `0`.
+fn need_space(prev: &TTKind, cur: &TTKind, next: Option<&TT>) -> bool {
+    use self::TTKind::{Token, Tree};
+    use self::TokenKind::{Ident, Keyword, Symbol};
+    let is_word = |tt: Option<&TTKind>| matches!(
+        tt, Some(&Token(Ident(_))) | Some(&Token(Keyword(_)))
+    );
+    match (prev, cur) {
+        (&Token(Symbol(symbol_type!("$"))), _) |
+        (_, &Token(Symbol(symbol_type!(",")))) |
+        (_, &Token(Symbol(symbol_type!(";")))) |
+        (&Token(Ident(_)), &Token(Symbol(symbol_type!("!")))) |
+        (&Token(Ident(_)), &Tree{ delim: Delimiter::Paren, .. }) |
+        (&Token(Ident(_)), &Tree{ delim: Delimiter::Bracket, .. }) |
+        (&Token(Symbol(symbol_type!("!"))), &Tree{ .. }) => false,
+        (_, &Token(Symbol(symbol_type!("::")))) |
+        (_, &Token(Symbol(symbol_type!(".")))) =>
+            !(is_word(Some(prev)) && is_word(next.map(|tt| &tt.0))),
+        (&Token(Symbol(symbol_type!("::"))), _) |
+        (&Token(Symbol(symbol_type!("."))), _) => !is_word(Some(cur)),
+        _ => true,
+    }
+}
+
+impl<'a> Display for Visibility<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Visibility::Private => Ok(()),
+            Visibility::Public => f.write_str("pub"),
+            Visibility::Crate => f.write_str("pub(crate)"),
+            Visibility::SelfMod => f.write_str("pub(self)"),
+            Visibility::Super => f.write_str("pub(super)"),
+            Visibility::InPath(ref path) => write!(f, "pub(in {})", path),
+        }
+    }
+}
+
+impl<'a> Display for UseTree<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            UseTree::Glob => f.write_char('*'),
+            UseTree::Name(UseName::Self_(_)) => f.write_str("self"),
+            UseTree::Name(UseName::Name{ ref name, ref alias }) => {
+                write_ident(f, name)?;
+                if let Some(ref alias) = *alias {
+                    f.write_str(" as ")?;
+                    write_ident(f, alias)?;
+                }
+                Ok(())
+            },
+            UseTree::Group(ref subs) => {
+                f.write_char('{')?;
+                write_sep(f, subs, ", ", |f, sub| sub.fmt(f))?;
+                f.write_char('}')
+            },
+            UseTree::Path{ ref path, ref sub } => {
+                path.root.fmt(f)?;
+                for comp in &path.comps {
+                    write_ident(f, comp)?;
+                    f.write_str("::")?;
+                }
+                sub.fmt(f)
+            },
+        }
+    }
+}
+
+impl<'a> Display for Meta<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Meta::Flag(ref name) => name.fmt(f),
+            Meta::KeyValue{ ref key, ref value } =>
+                write!(f, "{} = {}", key, value),
+            Meta::Sub{ ref name, ref subs } => {
+                write!(f, "{}(", name)?;
+                write_sep(f, subs, ", ", |f, sub| sub.fmt(f))?;
+                f.write_char(')')
+            },
+        }
+    }
+}
+
+impl<'a> Display for FuncParam<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            FuncParam::SelfMove{ is_mut: false } => f.write_str("self"),
+            FuncParam::SelfMove{ is_mut: true } => f.write_str("mut self"),
+            FuncParam::SelfRef{ is_mut: false } => f.write_str("&self"),
+            FuncParam::SelfRef{ is_mut: true } => f.write_str("&mut self"),
+            FuncParam::SelfAs(ref ty) => write!(f, "self: {}", ty),
+            FuncParam::Bind{ ref pat, ref ty } => match **ty {
+                Ty::Error => pat.fmt(f), // untyped lambda parameter
+                ref ty => write!(f, "{}: {}", pat, ty),
+            },
+        }
+    }
+}
+
+impl<'a> Display for TemplArg<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            TemplArg::Lifetime{ name, ref bound } => {
+                write_lt(f, name)?;
+                if let Some(ref bound) = *bound {
+                    f.write_str(": ")?;
+                    write_sep(f, bound, " + ", |f, &lt| write_lt(f, lt))?;
+                }
+                Ok(())
+            },
+            TemplArg::Ty{ ref name, ref bound, ref default } => {
+                write_ident(f, name)?;
+                if let Some(ref bound) = *bound {
+                    write!(f, ": {}", Bounds(bound))?;
+                }
+                if let Some(ref default) = *default {
+                    write!(f, " = {}", default)?;
+                }
+                Ok(())
+            },
+            TemplArg::Const{ ref name, ref ty, ref default } => {
+                f.write_str("const ")?;
+                write_ident(f, name)?;
+                write!(f, ": {}", ty)?;
+                if let Some(ref default) = *default {
+                    write!(f, " = {}", default)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl<'a> Display for Restrict<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        match *self {
+            Restrict::LifeBound{ lt, ref bound } => {
+                write_lt(f, lt)?;
+                f.write_str(": ")?;
+                write_sep(f, bound, " + ", |f, &lt| write_lt(f, lt))
+            },
+            Restrict::TraitBound{ ref for_lts, ref ty, ref bound } => {
+                write_for_lts(f, for_lts)?;
+                write!(f, "{}: {}", ty, Bounds(bound))
+            },
+        }
+    }
+}
+
+/// `<T, U>`, or nothing if empty.
+struct Templ<'b, 'a: 'b>(&'b [TemplArg<'a>]);
+
+impl<'b, 'a> Display for Templ<'b, 'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        f.write_char('<')?;
+        write_sep(f, self.0, ", ", |f, arg| arg.fmt(f))?;
+        f.write_char('>')
+    }
+}
+
+/// ` where T: U`, or nothing if there is no where clause.
+struct Whs<'b, 'a: 'b>(&'b OptWhere<'a>);
+
+impl<'b, 'a> Display for Whs<'b, 'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if let Some(ref whs) = *self.0 {
+            f.write_str(" where")?;
+            if !whs.is_empty() {
+                f.write_char(' ')?;
+                write_sep(f, whs, ", ", |f, r| r.fmt(f))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parameters of a function inside the parentheses.
+struct Params<'b, 'a: 'b>(&'b [FuncParam<'a>], bool);
+
+impl<'b, 'a> Display for Params<'b, 'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write_sep(f, self.0, ", ", |f, arg| arg.fmt(f))?;
+        if self.1 {
+            if !self.0.is_empty() {
+                f.write_str(", ")?;
+            }
+            f.write_str("...")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Display for FuncSig<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        if self.is_const {
+            f.write_str("const ")?;
+        }
+        if self.is_async {
+            f.write_str("async ")?;
+        }
+        if self.is_unsafe {
+            f.write_str("unsafe ")?;
+        }
+        write_abi(f, &self.abi)?;
+        f.write_str("fn ")?;
+        write_ident(f, &self.name)?;
+        write!(f, "{}({})", Templ(&self.templ), Params(&self.args, self.is_va))?;
+        if let Some(ref ty) = self.ret_ty {
+            write!(f, " -> {}", ty)?;
+        }
+        Whs(&self.whs).fmt(f)
+    }
+}
+
+impl<'a> Display for Expr<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut p = Printer::new(DEFAULT_INDENT);
+        p.print_expr(self);
+        f.write_str(&p.finish())
+    }
+}
+
+impl<'a> Display for Stmt<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut p = Printer::new(DEFAULT_INDENT);
+        p.print_stmt(self);
+        f.write_str(&p.finish())
+    }
+}
+
+impl<'a> Display for Mod<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        let mut p = Printer::new(DEFAULT_INDENT);
+        p.print_mod(self);
+        f.write_str(&p.finish())
+    }
+}
+
+fn unary_op_str(op: UnaryOp) -> &'static str {
+    match op {
+        UnaryOp::Try => "?",
+        UnaryOp::Neg => "-",
+        UnaryOp::Not => "!",
+        UnaryOp::Borrow => "&",
+        UnaryOp::BorrowMut => "&mut ",
+        UnaryOp::Deref => "*",
+    }
+}
+
+fn binary_op_str(op: BinaryOp) -> &'static str {
+    use self::BinaryOp::*;
+    match op {
+        Add => "+", Sub => "-", Mul => "*", Div => "/", Mod => "%",
+        And => "&", Or => "|", Xor => "^", Shl => "<<", Shr => ">>",
+        LogAnd => "&&", LogOr => "||",
+        Equ => "==", Ne => "!=", Lt => "<", Gt => ">", Le => "<=", Ge => ">=",
+        Place => "<-",
+        Assign => "=",
+        AddAssign => "+=", SubAssign => "-=", MulAssign => "*=",
+        DivAssign => "/=", ModAssign => "%=",
+        AndAssign => "&=", OrAssign => "|=", XorAssign => "^=",
+        ShlAssign => "<<=", ShrAssign => ">>=",
+    }
+}
+
+/// The indentation width used by `Display` implementations.
+pub const DEFAULT_INDENT: usize = 4;
+
+/// A pretty-printer rendering items, statements and expressions to formatted
+/// source, with `indent` spaces per level of indentation.
+#[derive(Debug, Clone)]
+pub struct Printer {
+    buf:    String,
+    indent: usize,
+    level:  usize,
+}
+
+impl Printer {
+    pub fn new(indent: usize) -> Self {
+        Printer{ buf: String::new(), indent, level: 0 }
+    }
+
+    /// Return the printed source.
+    pub fn finish(self) -> String {
+        self.buf
+    }
+
+    fn w<D: Display>(&mut self, d: D) {
+        write!(self.buf, "{}", d).unwrap();
+    }
+
+    /// Start a new line at the current indentation.
+    fn nl(&mut self) {
+        self.buf.push('\n');
+        for _ in 0..self.level * self.indent {
+            self.buf.push(' ');
+        }
+    }
+
+    /// Print `xs` in a brace block, one per line.
+    fn print_lines<T, F>(&mut self, xs: &[T], mut f: F)
+    where F: FnMut(&mut Self, &T) {
+        if xs.is_empty() {
+            return self.w("{}");
+        }
+        self.w('{');
+        self.level += 1;
+        for x in xs {
+            self.nl();
+            f(self, x);
+        }
+        self.level -= 1;
+        self.nl();
+        self.w('}');
+    }
+
+    /// Print an attribute. Doc comments are printed in the block form if
+    /// `inline` is set, since line comments end the line.
+    fn print_attr(&mut self, attr: &Attr, is_inner: bool, inline: bool) {
+        let mark = if is_inner { '!' } else { '/' };
+        match *attr {
+            Attr::Doc{ doc, .. } => {
+                match doc.strip_suffix('\n') {
+                    Some(line) if !inline && !line.contains('\n') =>
+                        self.w(format_args!("//{}{}", mark, line)),
+                    _ => self.w(format_args!(
+                        "/*{}{}*/", if is_inner { '!' } else { '*' }, doc,
+                    )),
+                }
+            },
+            Attr::Meta{ ref meta, .. } => if is_inner {
+                self.w(format_args!("#![{}]", meta))
+            } else {
+                self.w(format_args!("#[{}]", meta))
+            },
+            Attr::Tokens{ ref name, ref tts, .. } => {
+                self.w(if is_inner { "#![" } else { "#[" });
+                self.w(name);
+                for (i, tt) in tts.iter().enumerate() {
+                    match *tt {
+                        (TTKind::Tree{ .. }, _) if i == 0 => (),
+                        _ => self.w(' '),
+                    }
+                    write_tt(&mut self.buf, tt).unwrap();
+                }
+                self.w(']');
+            },
+        }
+    }
+
+    /// Print attributes before an item. Inner ones from the item body are
+    /// printed as outer ones, which have the same meaning.
+    fn print_attrs(&mut self, attrs: &[Attr], inline: bool) {
+        for attr in attrs {
+            self.print_attr(attr, false, inline);
+            if inline {
+                self.w(' ');
+            } else {
+                self.nl();
+            }
+        }
+    }
+
+    fn print_vis(&mut self, vis: &Visibility) {
+        if *vis != Visibility::Private {
+            self.w(format_args!("{} ", vis));
+        }
+    }
+
+    /// Print a module as inner attributes followed by items, each ending with
+    /// a newline.
+    pub fn print_mod(&mut self, m: &Mod) {
+        for attr in &m.attrs {
+            self.print_attr(attr, true, false);
+            self.nl();
+        }
+        for item in &m.items {
+            self.print_item(item);
+            self.nl();
+        }
+    }
+
+    pub fn print_item(&mut self, item: &Item) {
+        self.print_attrs(&item.attrs, false);
+        self.print_vis(&item.vis);
+        match item.detail {
+            ItemKind::ExternCrate{ ref name, ref alias } => {
+                self.w("extern crate ");
+                self.w(Id(name));
+                if let Some(ref alias) = *alias {
+                    self.w(format_args!(" as {}", Id(alias)));
+                }
+                self.w(';');
+            },
+            ItemKind::Use(ref tree) => self.w(format_args!("use {};", tree)),
+            ItemKind::ExternMod{ ref name } =>
+                self.w(format_args!("mod {};", Id(name))),
+            ItemKind::Mod{ ref name, ref items } => {
+                self.w(format_args!("mod {} ", Id(name)));
+                self.print_lines(items, Self::print_item);
+            },
+            ItemKind::FuncDecl{ ref sig } => self.w(format_args!("{};", sig)),
+            ItemKind::Func{ ref sig, ref body } => {
+                self.w(format_args!("{} ", sig));
+                self.print_expr(body);
+            },
+            ItemKind::Extern{ ref abi, ref items } => {
+                self.w(Abi(abi));
+                self.print_lines(items, Self::print_extern_item);
+            },
+            ItemKind::Type{ ref alias, ref templ, ref whs, ref origin } =>
+                self.w(format_args!(
+                    "type {}{}{} = {};", Id(alias), Templ(templ), Whs(whs), origin,
+                )),
+            ItemKind::StructUnit{ ref name, ref templ, ref whs } =>
+                self.w(format_args!(
+                    "struct {}{}{};", Id(name), Templ(templ), Whs(whs),
+                )),
+            ItemKind::StructTuple{ ref name, ref templ, ref elems, ref whs } => {
+                self.w(format_args!("struct {}{}", Id(name), Templ(templ)));
+                self.print_tuple_elems(elems);
+                self.w(format_args!("{};", Whs(whs)));
+            },
+            ItemKind::StructFields{ ref name, ref templ, ref whs, ref fields } => {
+                self.w(format_args!(
+                    "struct {}{}{} ", Id(name), Templ(templ), Whs(whs),
+                ));
+                self.print_lines(fields, |p, field| {
+                    p.print_field(field);
+                    p.w(',');
+                });
+            },
+            ItemKind::Union{ ref name, ref templ, ref whs, ref fields } => {
+                self.w(format_args!(
+                    "union {}{}{} ", Id(name), Templ(templ), Whs(whs),
+                ));
+                self.print_lines(fields, |p, field| {
+                    p.print_field(field);
+                    p.w(',');
+                });
+            },
+            ItemKind::Enum{ ref name, ref templ, ref whs, ref vars } => {
+                self.w(format_args!(
+                    "enum {}{}{} ", Id(name), Templ(templ), Whs(whs),
+                ));
+                self.print_lines(vars, |p, var| {
+                    p.print_enum_var(var);
+                    p.w(',');
+                });
+            },
+            ItemKind::Const{ ref name, ref ty, ref val } => {
+                self.w(format_args!("const {}: {} = ", Id(name), ty));
+                self.print_expr(val);
+                self.w(';');
+            },
+            ItemKind::Static{ is_mut, ref name, ref ty, ref val } => {
+                self.w(if is_mut { "static mut " } else { "static " });
+                self.w(format_args!("{}: {} = ", Id(name), ty));
+                self.print_expr(val);
+                self.w(';');
+            },
+            ItemKind::Trait{
+                is_unsafe, is_auto, ref name, ref templ, ref base, ref whs,
+                ref items,
+            } => {
+                if is_unsafe {
+                    self.w("unsafe ");
+                }
+                if is_auto {
+                    self.w("auto ");
+                }
+                self.w(format_args!("trait {}{}", Id(name), Templ(templ)));
+                if let Some(ref base) = *base {
+                    self.w(format_args!(": {}", Bounds(base)));
+                }
+                self.w(format_args!("{} ", Whs(whs)));
+                self.print_lines(items, Self::print_trait_item);
+            },
+            ItemKind::ImplType{
+                is_unsafe, ref templ, ref ty, ref whs, ref items,
+            } => {
+                if is_unsafe {
+                    self.w("unsafe ");
+                }
+                self.w(format_args!("impl{} {}{} ", Templ(templ), ty, Whs(whs)));
+                self.print_lines(items, Self::print_impl_item);
+            },
+            ItemKind::ImplTrait{
+                is_unsafe, ref templ, is_const, is_neg, ref tr, ref ty, ref whs,
+                ref items,
+            } => {
+                if is_unsafe {
+                    self.w("unsafe ");
+                }
+                self.w(format_args!(
+                    "impl{} {}{}{} for {}{} ",
+                    Templ(templ),
+                    if is_const { "const " } else { "" },
+                    if is_neg { "!" } else { "" },
+                    tr, ty, Whs(whs),
+                ));
+                self.print_lines(items, Self::print_impl_item);
+            },
+            ItemKind::MacroDef{ ref name, ref body } => {
+                self.w(format_args!("macro_rules! {} ", Id(name)));
+                self.print_tt_semi(body);
+            },
+            ItemKind::PluginInvoke(ref p) => {
+                self.w(format_args!("{}!", p.name));
+                if let Some(ref ident) = p.ident {
+                    self.w(format_args!(" {} ", Id(ident)));
+                }
+                self.print_tt_semi(&p.tt);
+            },
+        }
+    }
+
+    /// Print a token tree, followed by `;` if not braced.
+    fn print_tt_semi(&mut self, tt: &TT) {
+        write_tt(&mut self.buf, tt).unwrap();
+        match tt.0 {
+            TTKind::Tree{ delim: Delimiter::Brace, .. } => (),
+            _ => self.w(';'),
+        }
+    }
+
+    fn print_extern_item(&mut self, item: &ExternItem) {
+        self.print_attrs(&item.attrs, false);
+        self.print_vis(&item.vis);
+        match item.detail {
+            ExternItemKind::Func{ ref name, ref args, is_va, ref ret_ty } => {
+                self.w(format_args!("fn {}({})", Id(name), Params(args, is_va)));
+                if let Some(ref ty) = *ret_ty {
+                    self.w(format_args!(" -> {}", ty));
+                }
+            },
+            ExternItemKind::Static{ is_mut, ref name, ref ty } => {
+                self.w(if is_mut { "static mut " } else { "static " });
+                self.w(Id(name));
+                if let Some(ref ty) = *ty {
+                    self.w(format_args!(": {}", ty));
+                }
+            },
+        }
+        self.w(';');
+    }
+
+    fn print_trait_item(&mut self, item: &TraitItem) {
+        self.print_attrs(&item.attrs, false);
+        self.print_vis(&item.vis);
+        match item.detail {
+            TraitItemKind::AssocTy{
+                ref name, ref templ, ref bound, ref whs, ref default,
+            } => {
+                self.w(format_args!("type {}{}", Id(name), Templ(templ)));
+                if let Some(ref bound) = *bound {
+                    self.w(format_args!(": {}", Bounds(bound)));
+                }
+                self.w(Whs(whs));
+                if let Some(ref ty) = *default {
+                    self.w(format_args!(" = {}", ty));
+                }
+                self.w(';');
+            },
+            TraitItemKind::Func{ ref sig, ref default } => match *default {
+                None => self.w(format_args!("{};", sig)),
+                Some(ref body) => {
+                    self.w(format_args!("{} ", sig));
+                    self.print_expr(body);
+                },
+            },
+            TraitItemKind::Const{ ref name, ref ty, ref default } => {
+                self.w(format_args!("const {}: {}", Id(name), ty));
+                if let Some(ref val) = *default {
+                    self.w(" = ");
+                    self.print_expr(val);
+                }
+                self.w(';');
+            },
+        }
+    }
+
+    fn print_impl_item(&mut self, item: &ImplItem) {
+        self.print_attrs(&item.attrs, false);
+        self.print_vis(&item.vis);
+        match item.detail {
+            ImplItemKind::AssocTy{
+                is_default, ref name, ref templ, ref val, ref whs,
+            } => {
+                if is_default {
+                    self.w("default ");
+                }
+                self.w(format_args!(
+                    "type {}{} = {}{};", Id(name), Templ(templ), val, Whs(whs),
+                ));
+            },
+            ImplItemKind::Func{ is_default, ref sig, ref body } => {
+                if is_default {
+                    self.w("default ");
+                }
+                self.w(format_args!("{} ", sig));
+                self.print_expr(body);
+            },
+            ImplItemKind::Const{ is_default, ref name, ref ty, ref val } => {
+                if is_default {
+                    self.w("default ");
+                }
+                self.w(format_args!("const {}: {} = ", Id(name), ty));
+                self.print_expr(val);
+                self.w(';');
+            },
+        }
+    }
+
+    fn print_tuple_elems(&mut self, elems: &[StructTupleElem]) {
+        self.w('(');
+        for (i, elem) in elems.iter().enumerate() {
+            if i != 0 {
+                self.w(", ");
+            }
+            self.print_attrs(&elem.attrs, true);
+            self.print_vis(&elem.vis);
+            self.w(&elem.ty);
+        }
+        self.w(')');
+    }
+
+    fn print_field(&mut self, field: &StructField) {
+        self.print_attrs(&field.attrs, false);
+        self.print_vis(&field.vis);
+        self.w(format_args!("{}: {}", Id(&field.name), field.ty));
+    }
+
+    fn print_enum_var(&mut self, var: &EnumVar) {
+        match *var {
+            EnumVar::Unit{ ref attrs, ref name, ref disc } => {
+                self.print_attrs(attrs, false);
+                self.w(Id(name));
+                if let Some(ref disc) = *disc {
+                    self.w(" = ");
+                    self.print_expr(disc);
+                }
+            },
+            EnumVar::Tuple{ ref attrs, ref name, ref elems } => {
+                self.print_attrs(attrs, false);
+                self.w(Id(name));
+                self.print_tuple_elems(elems);
+            },
+            EnumVar::Struct{ ref attrs, ref name, ref fields } => {
+                self.print_attrs(attrs, false);
+                self.w(format_args!("{} ", Id(name)));
+                self.print_lines(fields, |p, field| {
+                    p.print_field(field);
+                    p.w(',');
+                });
+            },
+        }
+    }
+
+    pub fn print_stmt(&mut self, stmt: &Stmt) {
+        match *stmt {
+            Stmt::Item(ref item) => self.print_item(item),
+            Stmt::Let{ ref pat, ref ty, ref init } => {
+                self.w(format_args!("let {}", pat));
+                if let Some(ref ty) = *ty {
+                    self.w(format_args!(": {}", ty));
+                }
+                if let Some(ref init) = *init {
+                    self.w(" = ");
+                    self.print_expr(init);
+                }
+                self.w(';');
+            },
+            Stmt::Expr(ref expr) => self.print_expr(expr),
+            Stmt::Semi(ref expr) => {
+                self.print_expr(expr);
+                self.w(';');
+            },
+            Stmt::PluginInvoke(ref p) => {
+                self.w(format_args!("{}!", p.name));
+                if let Some(ref ident) = p.ident {
+                    self.w(format_args!(" {} ", Id(ident)));
+                }
+                self.print_tt_semi(&p.tt);
+            },
+        }
+    }
+
+    fn print_exprs(&mut self, exprs: &[Expr]) {
+        for (i, e) in exprs.iter().enumerate() {
+            if i != 0 {
+                self.w(", ");
+            }
+            self.print_expr(e);
+        }
+    }
+
+    fn print_label(&mut self, label: Option<Lifetime>) {
+        if let Some(label) = label {
+            self.w(format_args!("'{}: ", label));
+        }
+    }
+
+    pub fn print_expr(&mut self, expr: &Expr) {
+        match *expr {
+            Expr::Error => (),
+            Expr::Literal(ref lit) => self.w(lit),
+            Expr::Path(ref path) => self.w(path),
+            Expr::Tuple(ref elems) => {
+                self.w('(');
+                self.print_exprs(elems);
+                if elems.len() == 1 {
+                    self.w(',');
+                }
+                self.w(')');
+            },
+            Expr::Paren(ref e) => {
+                self.w('(');
+                self.print_expr(e);
+                self.w(')');
+            },
+            Expr::Struct{ ref ty, ref fields, ref base } => {
+                self.w(format_args!("{} {{", ty));
+                if fields.is_empty() && base.is_none() {
+                    return self.w('}');
+                }
+                for (i, field) in fields.iter().enumerate() {
+                    self.w(if i == 0 { " " } else { ", " });
+                    self.w(Id(&field.name));
+                    if let Some(ref e) = field.expr {
+                        self.w(": ");
+                        self.print_expr(e);
+                    }
+                }
+                if let Some(ref base) = *base {
+                    self.w(if fields.is_empty() { " .." } else { ", .." });
+                    self.print_expr(base);
+                }
+                self.w(" }");
+            },
+            Expr::Block{ ref attrs, ref stmts, ref ret } => {
+                if attrs.is_empty() && stmts.is_empty() && ret.is_none() {
+                    return self.w("{}");
+                }
+                self.w('{');
+                self.level += 1;
+                for attr in attrs {
+                    self.nl();
+                    self.print_attr(attr, true, false);
+                }
+                for stmt in stmts {
+                    self.nl();
+                    self.print_stmt(stmt);
+                }
+                if let Some(ref ret) = *ret {
+                    self.nl();
+                    self.print_expr(ret);
+                }
+                self.level -= 1;
+                self.nl();
+                self.w('}');
+            },
+            Expr::Unsafe(ref block) => {
+                self.w("unsafe ");
+                self.print_expr(block);
+            },
+            Expr::ConstBlock(ref block) => {
+                self.w("const ");
+                self.print_expr(block);
+            },
+            Expr::Async{ is_move, ref block } => {
+                self.w(if is_move { "async move " } else { "async " });
+                self.print_expr(block);
+            },
+            Expr::TryBlock(ref block) => {
+                self.w("try ");
+                self.print_expr(block);
+            },
+            Expr::Await(ref expr) => {
+                self.print_expr(expr);
+                self.w(".await");
+            },
+            Expr::MemberCall{ ref obj, ref func, ref args, .. } => {
+                self.print_expr(obj);
+                self.w(format_args!(".{}(", func));
+                self.print_exprs(args);
+                self.w(')');
+            },
+            Expr::StructField{ ref obj, ref field } => {
+                self.print_expr(obj);
+                self.w(format_args!(".{}", field));
+            },
+            Expr::TupleField{ ref obj, index, .. } => {
+                self.print_expr(obj);
+                self.w(format_args!(".{}", index));
+            },
+            Expr::Index{ ref obj, ref index, .. } => {
+                self.print_expr(obj);
+                self.w('[');
+                self.print_expr(index);
+                self.w(']');
+            },
+            Expr::ArrayFill{ ref elem, ref len } => {
+                self.w('[');
+                self.print_expr(elem);
+                self.w("; ");
+                self.print_expr(len);
+                self.w(']');
+            },
+            Expr::ArrayLit(ref elems) => {
+                self.w('[');
+                self.print_exprs(elems);
+                self.w(']');
+            },
+            Expr::UnaryOp{ op: UnaryOp::Try, ref expr, .. } => {
+                self.print_expr(expr);
+                self.w('?');
+            },
+            Expr::UnaryOp{ op, ref expr, .. } => {
+                self.w(unary_op_str(op));
+                match **expr { // `- -a`, `& &a`
+                    Expr::UnaryOp{ op, .. } if op != UnaryOp::Try => self.w(' '),
+                    _ => (),
+                }
+                self.print_expr(expr);
+            },
+            Expr::As{ ref expr, ref ty, .. } => {
+                self.print_expr(expr);
+                self.w(format_args!(" as {}", ty));
+            },
+            Expr::Colon{ ref expr, ref ty, .. } => {
+                self.print_expr(expr);
+                self.w(format_args!(": {}", ty));
+            },
+            Expr::BinaryOp{ op, ref l, ref r, .. } => {
+                self.print_expr(l);
+                self.w(format_args!(" {} ", binary_op_str(op)));
+                self.print_expr(r);
+            },
+            Expr::Range{ ref start, ref end, is_inclusive, .. } => {
+                if let Some(ref start) = *start {
+                    self.print_expr(start);
+                }
+                self.w(if is_inclusive { "..=" } else { ".." });
+                if let Some(ref end) = *end {
+                    self.print_expr(end);
+                }
+            },
+            Expr::Call{ ref func, ref args, .. } => {
+                self.print_expr(func);
+                self.w('(');
+                self.print_exprs(args);
+                self.w(')');
+            },
+            Expr::Lambda{ ref sig, ref body } => {
+                if sig.is_move {
+                    self.w("move ");
+                }
+                self.w(format_args!("|{}| ", Params(&sig.args, false)));
+                if let Some(ref ty) = sig.ret_ty {
+                    self.w(format_args!("-> {} ", ty));
+                }
+                self.print_expr(body);
+            },
+            Expr::Break{ label, ref expr, .. } => {
+                self.w("break");
+                if let Some(label) = label {
+                    self.w(format_args!(" '{}", label));
+                }
+                if let Some(ref e) = *expr {
+                    self.w(' ');
+                    self.print_expr(e);
+                }
+            },
+            Expr::Continue{ label, .. } => {
+                self.w("continue");
+                if let Some(label) = label {
+                    self.w(format_args!(" '{}", label));
+                }
+            },
+            Expr::Loop{ label, ref body } => {
+                self.print_label(label);
+                self.w("loop ");
+                self.print_expr(body);
+            },
+            Expr::While{ label, ref cond, ref body } => {
+                self.print_label(label);
+                self.w("while ");
+                self.print_expr(cond);
+                self.w(' ');
+                self.print_expr(body);
+            },
+            Expr::WhileLet{ label, ref pat, ref expr, ref body } => {
+                self.print_label(label);
+                self.w(format_args!("while let {} = ", pat));
+                self.print_expr(expr);
+                self.w(' ');
+                self.print_expr(body);
+            },
+            Expr::For{ label, ref pat, ref iter, ref body } => {
+                self.print_label(label);
+                self.w(format_args!("for {} in ", pat));
+                self.print_expr(iter);
+                self.w(' ');
+                self.print_expr(body);
+            },
+            Expr::If{ ref cond, ref then_expr, ref else_expr } => {
+                self.w("if ");
+                self.print_expr(cond);
+                self.w(' ');
+                self.print_expr(then_expr);
+                self.print_else(else_expr);
+            },
+            Expr::IfLet{ ref pat, ref match_expr, ref then_expr, ref else_expr } => {
+                self.w(format_args!("if let {} = ", pat));
+                self.print_expr(match_expr);
+                self.w(' ');
+                self.print_expr(then_expr);
+                self.print_else(else_expr);
+            },
+            Expr::Match{ ref expr, ref arms, .. } => {
+                self.w("match ");
+                self.print_expr(expr);
+                self.w(' ');
+                self.print_lines(arms, |p, arm| {
+                    for (i, pat) in arm.pats.iter().enumerate() {
+                        if i != 0 {
+                            p.w(" | ");
+                        }
+                        p.w(pat);
+                    }
+                    if let Some(ref cond) = arm.cond {
+                        p.w(" if ");
+                        p.print_expr(cond);
+                    }
+                    p.w(" => ");
+                    p.print_expr(&arm.expr);
+                    p.w(',');
+                });
+            },
+            Expr::Return{ ref expr, .. } => {
+                self.w("return");
+                if let Some(ref e) = *expr {
+                    self.w(' ');
+                    self.print_expr(e);
+                }
+            },
+            Expr::PluginInvoke(ref p) => self.w(p),
+        }
+    }
+
+    fn print_else(&mut self, else_expr: &Option<Box<Expr>>) {
+        if let Some(ref e) = *else_expr {
+            self.w(" else ");
+            self.print_expr(e);
+        }
+    }
+}
+
+/// An identifier, or nothing if missing.
+struct Id<'b, 'a: 'b>(&'b Ident<'a>);
+
+impl<'b, 'a> Display for Id<'b, 'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write_ident(f, self.0)
+    }
+}
+
+/// The ABI of an extern block, followed by a space.
+struct Abi<'b, 'a: 'b>(&'b ABI<'a>);
+
+impl<'b, 'a> Display for Abi<'b, 'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write_abi(f, self.0)
+    }
+}
@@ -0,0 +1,125 @@
+//! Conversion between TTs and `proc_macro2::TokenStream`, to feed parse
+//! results into `syn` or `quote`, or to parse the input of a procedural macro.
+//! Only available with the feature `proc-macro2`.
+//!
+//! # Example
+//!
+//! ```
+//! extern crate mair;
+//! extern crate proc_macro2;
+//! use mair::parse::lexer::Lexer;
+//! use mair::parse::parser::parse_tts;
+//! use mair::parse::proc_macro::to_token_stream;
+//!
+//! # fn main() {
+//! let s = "x >>= 1; f::<'a>(r#try) /// doc";
+//! let toks: Vec<_> = Lexer::new(s).map(Result::unwrap).collect();
+//! let ts = to_token_stream(&parse_tts(s, &toks).unwrap());
+//! let expected: proc_macro2::TokenStream = s.parse().unwrap();
+//! assert_eq!(ts.to_string(), expected.to_string());
+//! # }
+//! ```
+use proc_macro2::{
+    Delimiter as PmDelimiter, Group, Ident, Literal, Punct, Spacing, Span,
+    TokenStream, TokenTree,
+};
+use super::ast::{Delimiter, TT, TTKind};
+use super::error::ParseError;
+use super::lexer::{Lexer, TokenKind};
+use super::owned::OwnedTokens;
+use super::str_ptr_diff;
+
+/// Convert `tts` into a token stream with call-site spans. Documents become
+/// `#[doc = ".."]` attributes as `proc_macro` does.
+pub fn to_token_stream(tts: &[TT]) -> TokenStream {
+    let mut ret = vec![];
+    for (i, tt) in tts.iter().enumerate() {
+        push_tt(&mut ret, tt, tts.get(i + 1));
+    }
+    ret.into_iter().collect()
+}
+
+/// Push the token trees of `tt` to `v`. `next` decides the spacing of the
+/// last char of a symbol.
+fn push_tt(v: &mut Vec<TokenTree>, tt: &TT, next: Option<&TT>) {
+    let span = Span::call_site();
+    let (kind, loc) = match *tt {
+        (TTKind::Tree{ delim, ref tts }, _) => {
+            let delim = match delim {
+                Delimiter::Paren   => PmDelimiter::Parenthesis,
+                Delimiter::Bracket => PmDelimiter::Bracket,
+                Delimiter::Brace   => PmDelimiter::Brace,
+            };
+            v.push(Group::new(delim, to_token_stream(tts)).into());
+            return;
+        },
+        (TTKind::Token(ref kind), loc) => (kind, loc),
+    };
+    match *kind {
+        TokenKind::Ident(name) if loc.starts_with("r#") =>
+            v.push(Ident::new_raw(name, span).into()),
+        TokenKind::Ident(_) |
+        TokenKind::Keyword(_) => v.push(Ident::new(loc, span).into()),
+        TokenKind::Lifetime(name) => {
+            v.push(Punct::new('\'', Spacing::Joint).into());
+            v.push(Ident::new(name, span).into());
+        },
+        TokenKind::Literal(_) => match loc.parse::<Literal>() {
+            Ok(lit) => v.push(lit.into()),
+            // `true` and `false` are literals here.
+            Err(_) => v.push(Ident::new(loc, span).into()),
+        },
+        TokenKind::Symbol(_) => {
+            // Lifetimes start with the punct `'`.
+            let is_joint = match next {
+                Some(&(TTKind::Token(TokenKind::Symbol(_)), next_loc)) |
+                Some(&(TTKind::Token(TokenKind::Lifetime(_)), next_loc)) =>
+                    str_ptr_diff(next_loc, loc) == loc.len() as isize,
+                _ => false,
+            };
+            let mut it = loc.chars().peekable();
+            while let Some(c) = it.next() {
+                let spacing = if it.peek().is_some() || is_joint {
+                    Spacing::Joint
+                } else {
+                    Spacing::Alone
+                };
+                v.push(Punct::new(c, spacing).into());
+            }
+        },
+        TokenKind::InnerDoc(doc) | TokenKind::OuterDoc(doc) => {
+            v.push(Punct::new('#', Spacing::Alone).into());
+            if let TokenKind::InnerDoc(_) = *kind {
+                v.push(Punct::new('!', Spacing::Alone).into());
+            }
+            let attr: TokenStream = vec![
+                TokenTree::from(Ident::new("doc", span)),
+                Punct::new('=', Spacing::Alone).into(),
+                Literal::string(doc).into(),
+            ].into_iter().collect();
+            v.push(Group::new(PmDelimiter::Bracket, attr).into());
+        },
+        // Never in TTs.
+        TokenKind::Delimiter{ .. } |
+        TokenKind::Comment{ .. } |
+        TokenKind::Whitespace => (),
+    }
+}
+
+/// Convert `ts` back into tokens. The stream is printed and lexed again, so
+/// joint puncts like `>` `>` become the symbol `>>`. The printed source is
+/// kept with the tokens, since the parser needs it. Keywords are lexed as
+/// Rust 2015. Return Err if the lexer rejects a literal in `ts`.
+pub fn from_token_stream(ts: &TokenStream) -> Result<OwnedTokens, ParseError> {
+    let mut err = None;
+    let toks = OwnedTokens::new(ts.to_string(), |s| {
+        Lexer::new(s).collect::<Result<_, _>>().unwrap_or_else(|e| {
+            err = Some(ParseError::from_lexical(s, &e));
+            vec![]
+        })
+    });
+    match err {
+        Some(e) => Err(e),
+        None    => Ok(toks),
+    }
+}
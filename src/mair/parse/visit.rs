@@ -0,0 +1,777 @@
+//! Traversal over an immutable AST.
+//!
+//! Each `visit_*` method of `Visit` recurses into the children by calling the
+//! `walk_*` function of the same node by default. Override the methods you
+//! care about, and call the `walk_*` function inside to keep recursing.
+//!
+//! # Example
+//!
+//! ```
+//! use mair::parse::lexer::Lexer;
+//! use mair::parse::parser::{parse_tts, parse_crate};
+//! use mair::parse::ast::*;
+//! use mair::parse::visit::{self, Visit};
+//!
+//! struct HoleCounter(usize);
+//! impl<'a> Visit<'a> for HoleCounter {
+//!     fn visit_ty(&mut self, ty: &Ty<'a>) {
+//!         if let Ty::Hole = *ty {
+//!             self.0 += 1;
+//!         }
+//!         visit::walk_ty(self, ty);
+//!     }
+//! }
+//!
+//! let s = "fn f() { let x: Vec<_> = g::<_>(); } type T = (_, [_; 1]);";
+//! let toks: Vec<_> = Lexer::new(s).map(|t| t.unwrap()).collect();
+//! let (m, _) = parse_crate(s, parse_tts(s, &toks).unwrap());
+//! let mut c = HoleCounter(0);
+//! visit::walk_mod(&mut c, &m);
+//! assert_eq!(c.0, 4);
+//! ```
+use super::ast::*;
+
+pub trait Visit<'a> {
+    fn visit_mod(&mut self, m: &Mod<'a>) {
+        walk_mod(self, m)
+    }
+    fn visit_item(&mut self, item: &Item<'a>) {
+        walk_item(self, item)
+    }
+    fn visit_extern_item(&mut self, item: &ExternItem<'a>) {
+        walk_extern_item(self, item)
+    }
+    fn visit_trait_item(&mut self, item: &TraitItem<'a>) {
+        walk_trait_item(self, item)
+    }
+    fn visit_impl_item(&mut self, item: &ImplItem<'a>) {
+        walk_impl_item(self, item)
+    }
+    fn visit_vis(&mut self, vis: &Visibility<'a>) {
+        walk_vis(self, vis)
+    }
+    fn visit_use_tree(&mut self, tree: &UseTree<'a>) {
+        walk_use_tree(self, tree)
+    }
+    fn visit_func_sig(&mut self, sig: &FuncSig<'a>) {
+        walk_func_sig(self, sig)
+    }
+    fn visit_func_param(&mut self, param: &FuncParam<'a>) {
+        walk_func_param(self, param)
+    }
+    fn visit_struct_tuple_elem(&mut self, elem: &StructTupleElem<'a>) {
+        walk_struct_tuple_elem(self, elem)
+    }
+    fn visit_struct_field(&mut self, field: &StructField<'a>) {
+        walk_struct_field(self, field)
+    }
+    fn visit_enum_var(&mut self, var: &EnumVar<'a>) {
+        walk_enum_var(self, var)
+    }
+    fn visit_templ_arg(&mut self, arg: &TemplArg<'a>) {
+        walk_templ_arg(self, arg)
+    }
+    fn visit_restrict(&mut self, res: &Restrict<'a>) {
+        walk_restrict(self, res)
+    }
+    fn visit_ty(&mut self, ty: &Ty<'a>) {
+        walk_ty(self, ty)
+    }
+    fn visit_poly_trait(&mut self, tr: &PolyTrait<'a>) {
+        walk_poly_trait(self, tr)
+    }
+    fn visit_ty_apply(&mut self, app: &TyApply<'a>) {
+        walk_ty_apply(self, app)
+    }
+    fn visit_path(&mut self, path: &Path<'a>) {
+        walk_path(self, path)
+    }
+    fn visit_attr(&mut self, attr: &Attr<'a>) {
+        walk_attr(self, attr)
+    }
+    fn visit_stmt(&mut self, stmt: &Stmt<'a>) {
+        walk_stmt(self, stmt)
+    }
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        walk_expr(self, expr)
+    }
+    fn visit_match_arm(&mut self, arm: &MatchArm<'a>) {
+        walk_match_arm(self, arm)
+    }
+    fn visit_pat(&mut self, pat: &Pat<'a>) {
+        walk_pat(self, pat)
+    }
+    fn visit_literal(&mut self, lit: &Literal<'a>) {
+        walk_literal(self, lit)
+    }
+    fn visit_plugin_invoke(&mut self, p: &PluginInvoke<'a>) {
+        walk_plugin_invoke(self, p)
+    }
+    fn visit_tt(&mut self, tt: &TT<'a>) {
+        walk_tt(self, tt)
+    }
+}
+
+fn walk_bounds<'a, V: Visit<'a> + ?Sized>(v: &mut V, bounds: &[Bound<'a>]) {
+    for bound in bounds {
+        if let Bound::Trait(ref tr) = *bound {
+            v.visit_poly_trait(tr);
+        }
+    }
+}
+
+fn walk_opt_whs<'a, V: Visit<'a> + ?Sized>(v: &mut V, whs: &OptWhere<'a>) {
+    if let Some(ref whs) = *whs {
+        for res in whs {
+            v.visit_restrict(res);
+        }
+    }
+}
+
+fn walk_templ<'a, V: Visit<'a> + ?Sized>(v: &mut V, templ: &Template<'a>) {
+    for arg in templ {
+        v.visit_templ_arg(arg);
+    }
+}
+
+pub fn walk_mod<'a, V: Visit<'a> + ?Sized>(v: &mut V, m: &Mod<'a>) {
+    for attr in &m.attrs {
+        v.visit_attr(attr);
+    }
+    for item in &m.items {
+        v.visit_item(item);
+    }
+}
+
+pub fn walk_item<'a, V: Visit<'a> + ?Sized>(v: &mut V, item: &Item<'a>) {
+    use self::ItemKind::*;
+    for attr in &item.attrs {
+        v.visit_attr(attr);
+    }
+    v.visit_vis(&item.vis);
+    match item.detail {
+        ExternCrate{ .. } | ExternMod{ .. } => (),
+        Use(ref tree) => v.visit_use_tree(tree),
+        Mod{ ref items, .. } => for item in items {
+            v.visit_item(item);
+        },
+        FuncDecl{ ref sig } => v.visit_func_sig(sig),
+        Func{ ref sig, ref body } => {
+            v.visit_func_sig(sig);
+            v.visit_expr(body);
+        },
+        Extern{ ref items, .. } => for item in items {
+            v.visit_extern_item(item);
+        },
+        Type{ ref templ, ref whs, ref origin, .. } => {
+            walk_templ(v, templ);
+            walk_opt_whs(v, whs);
+            v.visit_ty(origin);
+        },
+        StructUnit{ ref templ, ref whs, .. } => {
+            walk_templ(v, templ);
+            walk_opt_whs(v, whs);
+        },
+        StructTuple{ ref templ, ref elems, ref whs, .. } => {
+            walk_templ(v, templ);
+            for elem in elems {
+                v.visit_struct_tuple_elem(elem);
+            }
+            walk_opt_whs(v, whs);
+        },
+        StructFields{ ref templ, ref whs, ref fields, .. } |
+        Union{ ref templ, ref whs, ref fields, .. } => {
+            walk_templ(v, templ);
+            walk_opt_whs(v, whs);
+            for field in fields {
+                v.visit_struct_field(field);
+            }
+        },
+        Enum{ ref templ, ref whs, ref vars, .. } => {
+            walk_templ(v, templ);
+            walk_opt_whs(v, whs);
+            for var in vars {
+                v.visit_enum_var(var);
+            }
+        },
+        Const{ ref ty, ref val, .. } | Static{ ref ty, ref val, .. } => {
+            v.visit_ty(ty);
+            v.visit_expr(val);
+        },
+        Trait{ ref templ, ref base, ref whs, ref items, .. } => {
+            walk_templ(v, templ);
+            if let Some(ref base) = *base {
+                walk_bounds(v, base);
+            }
+            walk_opt_whs(v, whs);
+            for item in items {
+                v.visit_trait_item(item);
+            }
+        },
+        ImplType{ ref templ, ref ty, ref whs, ref items, .. } => {
+            walk_templ(v, templ);
+            v.visit_ty(ty);
+            walk_opt_whs(v, whs);
+            for item in items {
+                v.visit_impl_item(item);
+            }
+        },
+        ImplTrait{ ref templ, ref tr, ref ty, ref whs, ref items, .. } => {
+            walk_templ(v, templ);
+            v.visit_ty(tr);
+            v.visit_ty(ty);
+            walk_opt_whs(v, whs);
+            for item in items {
+                v.visit_impl_item(item);
+            }
+        },
+        MacroDef{ ref body, .. } => v.visit_tt(body),
+        PluginInvoke(ref p) => v.visit_plugin_invoke(p),
+    }
+}
+
+pub fn walk_extern_item<'a, V: Visit<'a> + ?Sized>(
+    v:    &mut V,
+    item: &ExternItem<'a>,
+) {
+    for attr in &item.attrs {
+        v.visit_attr(attr);
+    }
+    v.visit_vis(&item.vis);
+    match item.detail {
+        ExternItemKind::Func{ ref args, ref ret_ty, .. } => {
+            for arg in args {
+                v.visit_func_param(arg);
+            }
+            if let Some(ref ty) = *ret_ty {
+                v.visit_ty(ty);
+            }
+        },
+        ExternItemKind::Static{ ref ty, .. } => if let Some(ref ty) = *ty {
+            v.visit_ty(ty);
+        },
+    }
+}
+
+pub fn walk_trait_item<'a, V: Visit<'a> + ?Sized>(
+    v:    &mut V,
+    item: &TraitItem<'a>,
+) {
+    for attr in &item.attrs {
+        v.visit_attr(attr);
+    }
+    v.visit_vis(&item.vis);
+    match item.detail {
+        TraitItemKind::AssocTy{
+            ref templ, ref bound, ref whs, ref default, ..
+        } => {
+            walk_templ(v, templ);
+            if let Some(ref bound) = *bound {
+                walk_bounds(v, bound);
+            }
+            walk_opt_whs(v, whs);
+            if let Some(ref ty) = *default {
+                v.visit_ty(ty);
+            }
+        },
+        TraitItemKind::Func{ ref sig, ref default } => {
+            v.visit_func_sig(sig);
+            if let Some(ref body) = *default {
+                v.visit_expr(body);
+            }
+        },
+        TraitItemKind::Const{ ref ty, ref default, .. } => {
+            v.visit_ty(ty);
+            if let Some(ref val) = *default {
+                v.visit_expr(val);
+            }
+        },
+    }
+}
+
+pub fn walk_impl_item<'a, V: Visit<'a> + ?Sized>(
+    v:    &mut V,
+    item: &ImplItem<'a>,
+) {
+    for attr in &item.attrs {
+        v.visit_attr(attr);
+    }
+    v.visit_vis(&item.vis);
+    match item.detail {
+        ImplItemKind::AssocTy{ ref templ, ref val, ref whs, .. } => {
+            walk_templ(v, templ);
+            v.visit_ty(val);
+            walk_opt_whs(v, whs);
+        },
+        ImplItemKind::Func{ ref sig, ref body, .. } => {
+            v.visit_func_sig(sig);
+            v.visit_expr(body);
+        },
+        ImplItemKind::Const{ ref ty, ref val, .. } => {
+            v.visit_ty(ty);
+            v.visit_expr(val);
+        },
+    }
+}
+
+pub fn walk_vis<'a, V: Visit<'a> + ?Sized>(v: &mut V, vis: &Visibility<'a>) {
+    if let Visibility::InPath(ref path) = *vis {
+        v.visit_path(path);
+    }
+}
+
+pub fn walk_use_tree<'a, V: Visit<'a> + ?Sized>(
+    v:    &mut V,
+    tree: &UseTree<'a>,
+) {
+    match *tree {
+        UseTree::Glob | UseTree::Name(_) => (),
+        UseTree::Group(ref trees) => for tree in trees {
+            v.visit_use_tree(tree);
+        },
+        UseTree::Path{ ref sub, .. } => v.visit_use_tree(sub),
+    }
+}
+
+pub fn walk_func_sig<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    sig: &FuncSig<'a>,
+) {
+    walk_templ(v, &sig.templ);
+    for arg in &sig.args {
+        v.visit_func_param(arg);
+    }
+    if let Some(ref ty) = sig.ret_ty {
+        v.visit_ty(ty);
+    }
+    walk_opt_whs(v, &sig.whs);
+}
+
+pub fn walk_func_param<'a, V: Visit<'a> + ?Sized>(
+    v:     &mut V,
+    param: &FuncParam<'a>,
+) {
+    match *param {
+        FuncParam::SelfMove{ .. } | FuncParam::SelfRef{ .. } => (),
+        FuncParam::SelfAs(ref ty) => v.visit_ty(ty),
+        FuncParam::Bind{ ref pat, ref ty } => {
+            v.visit_pat(pat);
+            v.visit_ty(ty);
+        },
+    }
+}
+
+pub fn walk_struct_tuple_elem<'a, V: Visit<'a> + ?Sized>(
+    v:    &mut V,
+    elem: &StructTupleElem<'a>,
+) {
+    for attr in &elem.attrs {
+        v.visit_attr(attr);
+    }
+    v.visit_vis(&elem.vis);
+    v.visit_ty(&elem.ty);
+}
+
+pub fn walk_struct_field<'a, V: Visit<'a> + ?Sized>(
+    v:     &mut V,
+    field: &StructField<'a>,
+) {
+    for attr in &field.attrs {
+        v.visit_attr(attr);
+    }
+    v.visit_vis(&field.vis);
+    v.visit_ty(&field.ty);
+}
+
+pub fn walk_enum_var<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    var: &EnumVar<'a>,
+) {
+    match *var {
+        EnumVar::Unit{ ref attrs, ref disc, .. } => {
+            for attr in attrs {
+                v.visit_attr(attr);
+            }
+            if let Some(ref disc) = *disc {
+                v.visit_expr(disc);
+            }
+        },
+        EnumVar::Tuple{ ref attrs, ref elems, .. } => {
+            for attr in attrs {
+                v.visit_attr(attr);
+            }
+            for elem in elems {
+                v.visit_struct_tuple_elem(elem);
+            }
+        },
+        EnumVar::Struct{ ref attrs, ref fields, .. } => {
+            for attr in attrs {
+                v.visit_attr(attr);
+            }
+            for field in fields {
+                v.visit_struct_field(field);
+            }
+        },
+    }
+}
+
+pub fn walk_templ_arg<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    arg: &TemplArg<'a>,
+) {
+    match *arg {
+        TemplArg::Lifetime{ .. } => (),
+        TemplArg::Ty{ ref bound, ref default, .. } => {
+            if let Some(ref bound) = *bound {
+                walk_bounds(v, bound);
+            }
+            if let Some(ref ty) = *default {
+                v.visit_ty(ty);
+            }
+        },
+        TemplArg::Const{ ref ty, ref default, .. } => {
+            v.visit_ty(ty);
+            if let Some(ref expr) = *default {
+                v.visit_expr(expr);
+            }
+        },
+    }
+}
+
+pub fn walk_restrict<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    res: &Restrict<'a>,
+) {
+    match *res {
+        Restrict::LifeBound{ .. } => (),
+        Restrict::TraitBound{ ref ty, ref bound, .. } => {
+            v.visit_ty(ty);
+            walk_bounds(v, bound);
+        },
+    }
+}
+
+pub fn walk_ty<'a, V: Visit<'a> + ?Sized>(v: &mut V, ty: &Ty<'a>) {
+    match *ty {
+        Ty::Error | Ty::Hole | Ty::Never | Ty::Self_ => (),
+        Ty::Traits(ref bounds) |
+        Ty::Dyn{ ref bounds, .. } |
+        Ty::Impl{ ref bounds, .. } => for tr in bounds {
+            v.visit_poly_trait(tr);
+        },
+        Ty::Apply(ref app) => v.visit_ty_apply(app),
+        Ty::Tuple(ref tys) => for ty in tys {
+            v.visit_ty(ty);
+        },
+        Ty::Paren(ref ty) |
+        Ty::Ref{ ref ty, .. } |
+        Ty::Ptr{ ref ty, .. } |
+        Ty::Slice(ref ty) => v.visit_ty(ty),
+        Ty::Array{ ref ty, ref size } => {
+            v.visit_ty(ty);
+            v.visit_expr(size);
+        },
+        Ty::Func(ref fty) => {
+            for arg in &fty.args {
+                v.visit_ty(&arg.ty);
+            }
+            if let Some(ref ty) = fty.ret_ty {
+                v.visit_ty(ty);
+            }
+        },
+        Ty::PluginInvoke(ref p) => v.visit_plugin_invoke(p),
+    }
+}
+
+pub fn walk_poly_trait<'a, V: Visit<'a> + ?Sized>(
+    v:  &mut V,
+    tr: &PolyTrait<'a>,
+) {
+    v.visit_ty_apply(&tr.tr);
+}
+
+pub fn walk_ty_apply<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    app: &TyApply<'a>,
+) {
+    match *app {
+        TyApply::Angle{ ref name, ref args } => {
+            v.visit_path(name);
+            for arg in args {
+                match *arg {
+                    TyApplyArg::Lifetime(_) => (),
+                    TyApplyArg::Ty(ref ty) |
+                    TyApplyArg::AssocTy{ ref ty, .. } => v.visit_ty(ty),
+                    TyApplyArg::Const(ref e) => v.visit_expr(e),
+                }
+            }
+        },
+        TyApply::Paren{ ref name, ref args, ref ret_ty } => {
+            v.visit_path(name);
+            for ty in args {
+                v.visit_ty(ty);
+            }
+            if let Some(ref ty) = *ret_ty {
+                v.visit_ty(ty);
+            }
+        },
+    }
+}
+
+pub fn walk_path<'a, V: Visit<'a> + ?Sized>(v: &mut V, path: &Path<'a>) {
+    if let Some(ref qself) = path.qself {
+        v.visit_ty(&qself.ty);
+        if let Some(ref tr) = qself.tr {
+            v.visit_ty_apply(tr);
+        }
+    }
+    for comp in &path.comps {
+        walk_path_comp(v, comp);
+    }
+}
+
+pub fn walk_attr<'a, V: Visit<'a> + ?Sized>(v: &mut V, attr: &Attr<'a>) {
+    fn walk_meta<'a, V: Visit<'a> + ?Sized>(v: &mut V, meta: &Meta<'a>) {
+        match *meta {
+            Meta::Flag(_) => (),
+            Meta::KeyValue{ ref value, .. } => v.visit_literal(value),
+            Meta::Sub{ ref subs, .. } => for meta in subs {
+                walk_meta(v, meta);
+            },
+        }
+    }
+    match *attr {
+        Attr::Doc{ .. } => (),
+        Attr::Meta{ ref meta, .. } => walk_meta(v, meta),
+        Attr::Tokens{ ref tts, .. } => for tt in tts {
+            v.visit_tt(tt);
+        },
+    }
+}
+
+pub fn walk_stmt<'a, V: Visit<'a> + ?Sized>(v: &mut V, stmt: &Stmt<'a>) {
+    match *stmt {
+        Stmt::Item(ref item) => v.visit_item(item),
+        Stmt::Let{ ref pat, ref ty, ref init } => {
+            v.visit_pat(pat);
+            if let Some(ref ty) = *ty {
+                v.visit_ty(ty);
+            }
+            if let Some(ref init) = *init {
+                v.visit_expr(init);
+            }
+        },
+        Stmt::Expr(ref expr) |
+        Stmt::Semi(ref expr) => v.visit_expr(expr),
+        Stmt::PluginInvoke(ref p) => v.visit_plugin_invoke(p),
+    }
+}
+
+pub fn walk_expr<'a, V: Visit<'a> + ?Sized>(v: &mut V, expr: &Expr<'a>) {
+    use self::Expr::*;
+    match *expr {
+        Error | Continue{ .. } => (),
+        Literal(ref lit) => v.visit_literal(lit),
+        Path(ref path) => v.visit_path(path),
+        Tuple(ref exprs) | ArrayLit(ref exprs) => for expr in exprs {
+            v.visit_expr(expr);
+        },
+        Paren(ref expr) |
+        Unsafe(ref expr) |
+        ConstBlock(ref expr) |
+        Async{ block: ref expr, .. } |
+        TryBlock(ref expr) |
+        Await(ref expr) |
+        TupleField{ obj: ref expr, .. } |
+        UnaryOp{ ref expr, .. } => v.visit_expr(expr),
+        Struct{ ref ty, ref fields, ref base } => {
+            v.visit_ty(ty);
+            for field in fields {
+                if let Some(ref expr) = field.expr {
+                    v.visit_expr(expr);
+                }
+            }
+            if let Some(ref base) = *base {
+                v.visit_expr(base);
+            }
+        },
+        Block{ ref attrs, ref stmts, ref ret } => {
+            for attr in attrs {
+                v.visit_attr(attr);
+            }
+            for stmt in stmts {
+                v.visit_stmt(stmt);
+            }
+            if let Some(ref ret) = *ret {
+                v.visit_expr(ret);
+            }
+        },
+        MemberCall{ ref obj, ref func, ref args, .. } => {
+            v.visit_expr(obj);
+            walk_path_comp(v, func);
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        },
+        StructField{ ref obj, ref field } => {
+            v.visit_expr(obj);
+            walk_path_comp(v, field);
+        },
+        Index{ obj: ref l, index: ref r, .. } |
+        ArrayFill{ elem: ref l, len: ref r } |
+        BinaryOp{ ref l, ref r, .. } => {
+            v.visit_expr(l);
+            v.visit_expr(r);
+        },
+        Range{ ref start, ref end, .. } => {
+            if let Some(ref start) = *start {
+                v.visit_expr(start);
+            }
+            if let Some(ref end) = *end {
+                v.visit_expr(end);
+            }
+        },
+        As{ ref expr, ref ty, .. } | Colon{ ref expr, ref ty, .. } => {
+            v.visit_expr(expr);
+            v.visit_ty(ty);
+        },
+        Call{ ref func, ref args, .. } => {
+            v.visit_expr(func);
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        },
+        Lambda{ ref sig, ref body } => {
+            for arg in &sig.args {
+                v.visit_func_param(arg);
+            }
+            if let Some(ref ty) = sig.ret_ty {
+                v.visit_ty(ty);
+            }
+            v.visit_expr(body);
+        },
+        Break{ ref expr, .. } | Return{ ref expr, .. } =>
+            if let Some(ref expr) = *expr {
+                v.visit_expr(expr);
+            },
+        Loop{ ref body, .. } => v.visit_expr(body),
+        While{ ref cond, ref body, .. } => {
+            v.visit_expr(cond);
+            v.visit_expr(body);
+        },
+        WhileLet{ ref pat, ref expr, ref body, .. } |
+        For{ ref pat, iter: ref expr, ref body, .. } => {
+            v.visit_pat(pat);
+            v.visit_expr(expr);
+            v.visit_expr(body);
+        },
+        If{ ref cond, ref then_expr, ref else_expr } => {
+            v.visit_expr(cond);
+            v.visit_expr(then_expr);
+            if let Some(ref else_expr) = *else_expr {
+                v.visit_expr(else_expr);
+            }
+        },
+        IfLet{ ref pat, ref match_expr, ref then_expr, ref else_expr } => {
+            v.visit_pat(pat);
+            v.visit_expr(match_expr);
+            v.visit_expr(then_expr);
+            if let Some(ref else_expr) = *else_expr {
+                v.visit_expr(else_expr);
+            }
+        },
+        Match{ ref expr, ref arms, .. } => {
+            v.visit_expr(expr);
+            for arm in arms {
+                v.visit_match_arm(arm);
+            }
+        },
+        PluginInvoke(ref p) => v.visit_plugin_invoke(p),
+    }
+}
+
+fn walk_path_comp<'a, V: Visit<'a> + ?Sized>(v: &mut V, comp: &PathComp<'a>) {
+    if let PathComp::Name{ hint: Some(ref hint), .. } = *comp {
+        for arg in hint {
+            match *arg {
+                TyHintArg::Lifetime(_) => (),
+                TyHintArg::Ty(ref ty) => v.visit_ty(ty),
+                TyHintArg::Const(ref e) => v.visit_expr(e),
+            }
+        }
+    }
+}
+
+pub fn walk_match_arm<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    arm: &MatchArm<'a>,
+) {
+    for pat in &arm.pats {
+        v.visit_pat(pat);
+    }
+    if let Some(ref cond) = arm.cond {
+        v.visit_expr(cond);
+    }
+    v.visit_expr(&arm.expr);
+}
+
+pub fn walk_pat<'a, V: Visit<'a> + ?Sized>(v: &mut V, pat: &Pat<'a>) {
+    match *pat {
+        Pat::Hole => (),
+        Pat::BindLike{ ref pat, .. } => if let Some(ref pat) = *pat {
+            v.visit_pat(pat);
+        },
+        Pat::Path(ref path) => v.visit_path(path),
+        Pat::Literal(ref lit) => v.visit_literal(lit),
+        Pat::Range(ref l, ref r) => {
+            v.visit_literal(l);
+            v.visit_literal(r);
+        },
+        Pat::Ref{ ref pat, .. } | Pat::Paren(ref pat) => v.visit_pat(pat),
+        Pat::Tuple(ref pats) | Pat::Or(ref pats) => for pat in pats {
+            v.visit_pat(pat);
+        },
+        Pat::DestructTuple{ ref name, ref elems } => {
+            v.visit_path(name);
+            for pat in elems {
+                v.visit_pat(pat);
+            }
+        },
+        Pat::DestructNormal{ ref name, ref fields, .. } => {
+            v.visit_path(name);
+            for field in fields {
+                if let Some(ref pat) = field.pat {
+                    v.visit_pat(pat);
+                }
+            }
+        },
+        Pat::PluginInvoke(ref p) => v.visit_plugin_invoke(p),
+    }
+}
+
+pub fn walk_literal<'a, V: Visit<'a> + ?Sized>(
+    v:   &mut V,
+    lit: &Literal<'a>,
+) {
+    match *lit {
+        Literal::IntLike{ ty: Some(ref ty), .. } |
+        Literal::FloatLike{ ty: Some(ref ty), .. } => v.visit_ty(ty),
+        _ => (),
+    }
+}
+
+pub fn walk_plugin_invoke<'a, V: Visit<'a> + ?Sized>(
+    v: &mut V,
+    p: &PluginInvoke<'a>,
+) {
+    v.visit_path(&p.name);
+    v.visit_tt(&p.tt);
+}
+
+pub fn walk_tt<'a, V: Visit<'a> + ?Sized>(v: &mut V, tt: &TT<'a>) {
+    if let TTKind::Tree{ ref tts, .. } = tt.0 {
+        for tt in tts {
+            v.visit_tt(tt);
+        }
+    }
+}
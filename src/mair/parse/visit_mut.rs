@@ -0,0 +1,829 @@
+//! Traversal over a mutable AST, which can rewrite nodes in place.
+//!
+//! It is the same as `visit` except that all nodes are passed by mutable
+//! reference.
+//!
+//! # Example
+//!
+//! ```
+//! use mair::parse::lexer::Lexer;
+//! use mair::parse::parser::{parse_tts, parse_crate};
+//! use mair::parse::ast::*;
+//! use mair::parse::visit_mut::{self, VisitMut};
+//!
+//! struct FillHole;
+//! impl<'a> VisitMut<'a> for FillHole {
+//!     fn visit_ty_mut(&mut self, ty: &mut Ty<'a>) {
+//!         if let Ty::Hole = *ty {
+//!             *ty = Ty::from_name("u8");
+//!         }
+//!         visit_mut::walk_ty_mut(self, ty);
+//!     }
+//! }
+//!
+//! let s = "fn f() { let x: Vec<_> = g::<_>(); } type T = (_, [_; 1]);";
+//! let toks: Vec<_> = Lexer::new(s).map(|t| t.unwrap()).collect();
+//! let (mut m, _) = parse_crate(s, parse_tts(s, &toks).unwrap());
+//! visit_mut::walk_mod_mut(&mut FillHole, &mut m);
+//! let dbg = format!("{:?}", m);
+//! assert!(!dbg.contains("Hole"));
+//! assert_eq!(dbg.matches("\"u8\"").count(), 4);
+//! ```
+use super::ast::*;
+
+pub trait VisitMut<'a> {
+    fn visit_mod_mut(&mut self, m: &mut Mod<'a>) {
+        walk_mod_mut(self, m)
+    }
+    fn visit_item_mut(&mut self, item: &mut Item<'a>) {
+        walk_item_mut(self, item)
+    }
+    fn visit_extern_item_mut(&mut self, item: &mut ExternItem<'a>) {
+        walk_extern_item_mut(self, item)
+    }
+    fn visit_trait_item_mut(&mut self, item: &mut TraitItem<'a>) {
+        walk_trait_item_mut(self, item)
+    }
+    fn visit_impl_item_mut(&mut self, item: &mut ImplItem<'a>) {
+        walk_impl_item_mut(self, item)
+    }
+    fn visit_vis_mut(&mut self, vis: &mut Visibility<'a>) {
+        walk_vis_mut(self, vis)
+    }
+    fn visit_use_tree_mut(&mut self, tree: &mut UseTree<'a>) {
+        walk_use_tree_mut(self, tree)
+    }
+    fn visit_func_sig_mut(&mut self, sig: &mut FuncSig<'a>) {
+        walk_func_sig_mut(self, sig)
+    }
+    fn visit_func_param_mut(&mut self, param: &mut FuncParam<'a>) {
+        walk_func_param_mut(self, param)
+    }
+    fn visit_struct_tuple_elem_mut(&mut self, elem: &mut StructTupleElem<'a>) {
+        walk_struct_tuple_elem_mut(self, elem)
+    }
+    fn visit_struct_field_mut(&mut self, field: &mut StructField<'a>) {
+        walk_struct_field_mut(self, field)
+    }
+    fn visit_enum_var_mut(&mut self, var: &mut EnumVar<'a>) {
+        walk_enum_var_mut(self, var)
+    }
+    fn visit_templ_arg_mut(&mut self, arg: &mut TemplArg<'a>) {
+        walk_templ_arg_mut(self, arg)
+    }
+    fn visit_restrict_mut(&mut self, res: &mut Restrict<'a>) {
+        walk_restrict_mut(self, res)
+    }
+    fn visit_ty_mut(&mut self, ty: &mut Ty<'a>) {
+        walk_ty_mut(self, ty)
+    }
+    fn visit_poly_trait_mut(&mut self, tr: &mut PolyTrait<'a>) {
+        walk_poly_trait_mut(self, tr)
+    }
+    fn visit_ty_apply_mut(&mut self, app: &mut TyApply<'a>) {
+        walk_ty_apply_mut(self, app)
+    }
+    fn visit_path_mut(&mut self, path: &mut Path<'a>) {
+        walk_path_mut(self, path)
+    }
+    fn visit_attr_mut(&mut self, attr: &mut Attr<'a>) {
+        walk_attr_mut(self, attr)
+    }
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt<'a>) {
+        walk_stmt_mut(self, stmt)
+    }
+    fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+        walk_expr_mut(self, expr)
+    }
+    fn visit_match_arm_mut(&mut self, arm: &mut MatchArm<'a>) {
+        walk_match_arm_mut(self, arm)
+    }
+    fn visit_pat_mut(&mut self, pat: &mut Pat<'a>) {
+        walk_pat_mut(self, pat)
+    }
+    fn visit_literal_mut(&mut self, lit: &mut Literal<'a>) {
+        walk_literal_mut(self, lit)
+    }
+    fn visit_plugin_invoke_mut(&mut self, p: &mut PluginInvoke<'a>) {
+        walk_plugin_invoke_mut(self, p)
+    }
+    fn visit_tt_mut(&mut self, tt: &mut TT<'a>) {
+        walk_tt_mut(self, tt)
+    }
+}
+
+fn walk_bounds_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:      &mut V,
+    bounds: &mut [Bound<'a>],
+) {
+    for bound in bounds {
+        if let Bound::Trait(ref mut tr) = *bound {
+            v.visit_poly_trait_mut(tr);
+        }
+    }
+}
+
+fn walk_opt_whs_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    whs: &mut OptWhere<'a>,
+) {
+    if let Some(ref mut whs) = *whs {
+        for res in whs {
+            v.visit_restrict_mut(res);
+        }
+    }
+}
+
+fn walk_templ_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:     &mut V,
+    templ: &mut Template<'a>,
+) {
+    for arg in templ {
+        v.visit_templ_arg_mut(arg);
+    }
+}
+
+pub fn walk_mod_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, m: &mut Mod<'a>) {
+    for attr in &mut m.attrs {
+        v.visit_attr_mut(attr);
+    }
+    for item in &mut m.items {
+        v.visit_item_mut(item);
+    }
+}
+
+pub fn walk_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    item: &mut Item<'a>,
+) {
+    use self::ItemKind::*;
+    for attr in &mut item.attrs {
+        v.visit_attr_mut(attr);
+    }
+    v.visit_vis_mut(&mut item.vis);
+    match item.detail {
+        ExternCrate{ .. } | ExternMod{ .. } => (),
+        Use(ref mut tree) => v.visit_use_tree_mut(tree),
+        Mod{ ref mut items, .. } => for item in items {
+            v.visit_item_mut(item);
+        },
+        FuncDecl{ ref mut sig } => v.visit_func_sig_mut(sig),
+        Func{ ref mut sig, ref mut body } => {
+            v.visit_func_sig_mut(sig);
+            v.visit_expr_mut(body);
+        },
+        Extern{ ref mut items, .. } => for item in items {
+            v.visit_extern_item_mut(item);
+        },
+        Type{ ref mut templ, ref mut whs, ref mut origin, .. } => {
+            walk_templ_mut(v, templ);
+            walk_opt_whs_mut(v, whs);
+            v.visit_ty_mut(origin);
+        },
+        StructUnit{ ref mut templ, ref mut whs, .. } => {
+            walk_templ_mut(v, templ);
+            walk_opt_whs_mut(v, whs);
+        },
+        StructTuple{ ref mut templ, ref mut elems, ref mut whs, .. } => {
+            walk_templ_mut(v, templ);
+            for elem in elems {
+                v.visit_struct_tuple_elem_mut(elem);
+            }
+            walk_opt_whs_mut(v, whs);
+        },
+        StructFields{ ref mut templ, ref mut whs, ref mut fields, .. } |
+        Union{ ref mut templ, ref mut whs, ref mut fields, .. } => {
+            walk_templ_mut(v, templ);
+            walk_opt_whs_mut(v, whs);
+            for field in fields {
+                v.visit_struct_field_mut(field);
+            }
+        },
+        Enum{ ref mut templ, ref mut whs, ref mut vars, .. } => {
+            walk_templ_mut(v, templ);
+            walk_opt_whs_mut(v, whs);
+            for var in vars {
+                v.visit_enum_var_mut(var);
+            }
+        },
+        Const{ ref mut ty, ref mut val, .. } |
+        Static{ ref mut ty, ref mut val, .. } => {
+            v.visit_ty_mut(ty);
+            v.visit_expr_mut(val);
+        },
+        Trait{
+            ref mut templ, ref mut base, ref mut whs, ref mut items, ..
+        } => {
+            walk_templ_mut(v, templ);
+            if let Some(ref mut base) = *base {
+                walk_bounds_mut(v, base);
+            }
+            walk_opt_whs_mut(v, whs);
+            for item in items {
+                v.visit_trait_item_mut(item);
+            }
+        },
+        ImplType{
+            ref mut templ, ref mut ty, ref mut whs, ref mut items, ..
+        } => {
+            walk_templ_mut(v, templ);
+            v.visit_ty_mut(ty);
+            walk_opt_whs_mut(v, whs);
+            for item in items {
+                v.visit_impl_item_mut(item);
+            }
+        },
+        ImplTrait{
+            ref mut templ, ref mut tr, ref mut ty, ref mut whs, ref mut items,
+            ..
+        } => {
+            walk_templ_mut(v, templ);
+            v.visit_ty_mut(tr);
+            v.visit_ty_mut(ty);
+            walk_opt_whs_mut(v, whs);
+            for item in items {
+                v.visit_impl_item_mut(item);
+            }
+        },
+        MacroDef{ ref mut body, .. } => v.visit_tt_mut(body),
+        PluginInvoke(ref mut p) => v.visit_plugin_invoke_mut(p),
+    }
+}
+
+pub fn walk_extern_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    item: &mut ExternItem<'a>,
+) {
+    for attr in &mut item.attrs {
+        v.visit_attr_mut(attr);
+    }
+    v.visit_vis_mut(&mut item.vis);
+    match item.detail {
+        ExternItemKind::Func{ ref mut args, ref mut ret_ty, .. } => {
+            for arg in args {
+                v.visit_func_param_mut(arg);
+            }
+            if let Some(ref mut ty) = *ret_ty {
+                v.visit_ty_mut(ty);
+            }
+        },
+        ExternItemKind::Static{ ref mut ty, .. } =>
+            if let Some(ref mut ty) = *ty {
+                v.visit_ty_mut(ty);
+            },
+    }
+}
+
+pub fn walk_trait_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    item: &mut TraitItem<'a>,
+) {
+    for attr in &mut item.attrs {
+        v.visit_attr_mut(attr);
+    }
+    v.visit_vis_mut(&mut item.vis);
+    match item.detail {
+        TraitItemKind::AssocTy{
+            ref mut templ, ref mut bound, ref mut whs, ref mut default, ..
+        } => {
+            walk_templ_mut(v, templ);
+            if let Some(ref mut bound) = *bound {
+                walk_bounds_mut(v, bound);
+            }
+            walk_opt_whs_mut(v, whs);
+            if let Some(ref mut ty) = *default {
+                v.visit_ty_mut(ty);
+            }
+        },
+        TraitItemKind::Func{ ref mut sig, ref mut default } => {
+            v.visit_func_sig_mut(sig);
+            if let Some(ref mut body) = *default {
+                v.visit_expr_mut(body);
+            }
+        },
+        TraitItemKind::Const{ ref mut ty, ref mut default, .. } => {
+            v.visit_ty_mut(ty);
+            if let Some(ref mut val) = *default {
+                v.visit_expr_mut(val);
+            }
+        },
+    }
+}
+
+pub fn walk_impl_item_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    item: &mut ImplItem<'a>,
+) {
+    for attr in &mut item.attrs {
+        v.visit_attr_mut(attr);
+    }
+    v.visit_vis_mut(&mut item.vis);
+    match item.detail {
+        ImplItemKind::AssocTy{
+            ref mut templ, ref mut val, ref mut whs, ..
+        } => {
+            walk_templ_mut(v, templ);
+            v.visit_ty_mut(val);
+            walk_opt_whs_mut(v, whs);
+        },
+        ImplItemKind::Func{ ref mut sig, ref mut body, .. } => {
+            v.visit_func_sig_mut(sig);
+            v.visit_expr_mut(body);
+        },
+        ImplItemKind::Const{ ref mut ty, ref mut val, .. } => {
+            v.visit_ty_mut(ty);
+            v.visit_expr_mut(val);
+        },
+    }
+}
+
+pub fn walk_vis_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    vis: &mut Visibility<'a>,
+) {
+    if let Visibility::InPath(ref mut path) = *vis {
+        v.visit_path_mut(path);
+    }
+}
+
+pub fn walk_use_tree_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    tree: &mut UseTree<'a>,
+) {
+    match *tree {
+        UseTree::Glob | UseTree::Name(_) => (),
+        UseTree::Group(ref mut trees) => for tree in trees {
+            v.visit_use_tree_mut(tree);
+        },
+        UseTree::Path{ ref mut sub, .. } => v.visit_use_tree_mut(sub),
+    }
+}
+
+pub fn walk_func_sig_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    sig: &mut FuncSig<'a>,
+) {
+    walk_templ_mut(v, &mut sig.templ);
+    for arg in &mut sig.args {
+        v.visit_func_param_mut(arg);
+    }
+    if let Some(ref mut ty) = sig.ret_ty {
+        v.visit_ty_mut(ty);
+    }
+    walk_opt_whs_mut(v, &mut sig.whs);
+}
+
+pub fn walk_func_param_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:     &mut V,
+    param: &mut FuncParam<'a>,
+) {
+    match *param {
+        FuncParam::SelfMove{ .. } | FuncParam::SelfRef{ .. } => (),
+        FuncParam::SelfAs(ref mut ty) => v.visit_ty_mut(ty),
+        FuncParam::Bind{ ref mut pat, ref mut ty } => {
+            v.visit_pat_mut(pat);
+            v.visit_ty_mut(ty);
+        },
+    }
+}
+
+pub fn walk_struct_tuple_elem_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    elem: &mut StructTupleElem<'a>,
+) {
+    for attr in &mut elem.attrs {
+        v.visit_attr_mut(attr);
+    }
+    v.visit_vis_mut(&mut elem.vis);
+    v.visit_ty_mut(&mut elem.ty);
+}
+
+pub fn walk_struct_field_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:     &mut V,
+    field: &mut StructField<'a>,
+) {
+    for attr in &mut field.attrs {
+        v.visit_attr_mut(attr);
+    }
+    v.visit_vis_mut(&mut field.vis);
+    v.visit_ty_mut(&mut field.ty);
+}
+
+pub fn walk_enum_var_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    var: &mut EnumVar<'a>,
+) {
+    match *var {
+        EnumVar::Unit{ ref mut attrs, ref mut disc, .. } => {
+            for attr in attrs {
+                v.visit_attr_mut(attr);
+            }
+            if let Some(ref mut disc) = *disc {
+                v.visit_expr_mut(disc);
+            }
+        },
+        EnumVar::Tuple{ ref mut attrs, ref mut elems, .. } => {
+            for attr in attrs {
+                v.visit_attr_mut(attr);
+            }
+            for elem in elems {
+                v.visit_struct_tuple_elem_mut(elem);
+            }
+        },
+        EnumVar::Struct{ ref mut attrs, ref mut fields, .. } => {
+            for attr in attrs {
+                v.visit_attr_mut(attr);
+            }
+            for field in fields {
+                v.visit_struct_field_mut(field);
+            }
+        },
+    }
+}
+
+pub fn walk_templ_arg_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    arg: &mut TemplArg<'a>,
+) {
+    match *arg {
+        TemplArg::Lifetime{ .. } => (),
+        TemplArg::Ty{ ref mut bound, ref mut default, .. } => {
+            if let Some(ref mut bound) = *bound {
+                walk_bounds_mut(v, bound);
+            }
+            if let Some(ref mut ty) = *default {
+                v.visit_ty_mut(ty);
+            }
+        },
+        TemplArg::Const{ ref mut ty, ref mut default, .. } => {
+            v.visit_ty_mut(ty);
+            if let Some(ref mut expr) = *default {
+                v.visit_expr_mut(expr);
+            }
+        },
+    }
+}
+
+pub fn walk_restrict_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    res: &mut Restrict<'a>,
+) {
+    match *res {
+        Restrict::LifeBound{ .. } => (),
+        Restrict::TraitBound{ ref mut ty, ref mut bound, .. } => {
+            v.visit_ty_mut(ty);
+            walk_bounds_mut(v, bound);
+        },
+    }
+}
+
+pub fn walk_ty_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, ty: &mut Ty<'a>) {
+    match *ty {
+        Ty::Error | Ty::Hole | Ty::Never | Ty::Self_ => (),
+        Ty::Traits(ref mut bounds) |
+        Ty::Dyn{ ref mut bounds, .. } |
+        Ty::Impl{ ref mut bounds, .. } => for tr in bounds {
+            v.visit_poly_trait_mut(tr);
+        },
+        Ty::Apply(ref mut app) => v.visit_ty_apply_mut(app),
+        Ty::Tuple(ref mut tys) => for ty in tys {
+            v.visit_ty_mut(ty);
+        },
+        Ty::Paren(ref mut ty) |
+        Ty::Ref{ ref mut ty, .. } |
+        Ty::Ptr{ ref mut ty, .. } |
+        Ty::Slice(ref mut ty) => v.visit_ty_mut(ty),
+        Ty::Array{ ref mut ty, ref mut size } => {
+            v.visit_ty_mut(ty);
+            v.visit_expr_mut(size);
+        },
+        Ty::Func(ref mut fty) => {
+            for arg in &mut fty.args {
+                v.visit_ty_mut(&mut arg.ty);
+            }
+            if let Some(ref mut ty) = fty.ret_ty {
+                v.visit_ty_mut(ty);
+            }
+        },
+        Ty::PluginInvoke(ref mut p) => v.visit_plugin_invoke_mut(p),
+    }
+}
+
+pub fn walk_poly_trait_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:  &mut V,
+    tr: &mut PolyTrait<'a>,
+) {
+    v.visit_ty_apply_mut(&mut tr.tr);
+}
+
+pub fn walk_ty_apply_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    app: &mut TyApply<'a>,
+) {
+    match *app {
+        TyApply::Angle{ ref mut name, ref mut args } => {
+            v.visit_path_mut(name);
+            for arg in args {
+                match *arg {
+                    TyApplyArg::Lifetime(_) => (),
+                    TyApplyArg::Ty(ref mut ty) |
+                    TyApplyArg::AssocTy{ ref mut ty, .. } => v.visit_ty_mut(ty),
+                    TyApplyArg::Const(ref mut e) => v.visit_expr_mut(e),
+                }
+            }
+        },
+        TyApply::Paren{ ref mut name, ref mut args, ref mut ret_ty } => {
+            v.visit_path_mut(name);
+            for ty in args {
+                v.visit_ty_mut(ty);
+            }
+            if let Some(ref mut ty) = *ret_ty {
+                v.visit_ty_mut(ty);
+            }
+        },
+    }
+}
+
+pub fn walk_path_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    path: &mut Path<'a>,
+) {
+    if let Some(ref mut qself) = path.qself {
+        v.visit_ty_mut(&mut qself.ty);
+        if let Some(ref mut tr) = qself.tr {
+            v.visit_ty_apply_mut(tr);
+        }
+    }
+    for comp in &mut path.comps {
+        walk_path_comp_mut(v, comp);
+    }
+}
+
+pub fn walk_attr_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    attr: &mut Attr<'a>,
+) {
+    fn walk_meta_mut<'a, V: VisitMut<'a> + ?Sized>(
+        v:    &mut V,
+        meta: &mut Meta<'a>,
+    ) {
+        match *meta {
+            Meta::Flag(_) => (),
+            Meta::KeyValue{ ref mut value, .. } => v.visit_literal_mut(value),
+            Meta::Sub{ ref mut subs, .. } => for meta in subs {
+                walk_meta_mut(v, meta);
+            },
+        }
+    }
+    match *attr {
+        Attr::Doc{ .. } => (),
+        Attr::Meta{ ref mut meta, .. } => walk_meta_mut(v, meta),
+        Attr::Tokens{ ref mut tts, .. } => for tt in tts {
+            v.visit_tt_mut(tt);
+        },
+    }
+}
+
+pub fn walk_stmt_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    stmt: &mut Stmt<'a>,
+) {
+    match *stmt {
+        Stmt::Item(ref mut item) => v.visit_item_mut(item),
+        Stmt::Let{ ref mut pat, ref mut ty, ref mut init } => {
+            v.visit_pat_mut(pat);
+            if let Some(ref mut ty) = *ty {
+                v.visit_ty_mut(ty);
+            }
+            if let Some(ref mut init) = *init {
+                v.visit_expr_mut(init);
+            }
+        },
+        Stmt::Expr(ref mut expr) |
+        Stmt::Semi(ref mut expr) => v.visit_expr_mut(expr),
+        Stmt::PluginInvoke(ref mut p) => v.visit_plugin_invoke_mut(p),
+    }
+}
+
+pub fn walk_expr_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    expr: &mut Expr<'a>,
+) {
+    use self::Expr::*;
+    match *expr {
+        Error | Continue{ .. } => (),
+        Literal(ref mut lit) => v.visit_literal_mut(lit),
+        Path(ref mut path) => v.visit_path_mut(path),
+        Tuple(ref mut exprs) | ArrayLit(ref mut exprs) => for expr in exprs {
+            v.visit_expr_mut(expr);
+        },
+        Paren(ref mut expr) |
+        Unsafe(ref mut expr) |
+        ConstBlock(ref mut expr) |
+        Async{ block: ref mut expr, .. } |
+        TryBlock(ref mut expr) |
+        Await(ref mut expr) |
+        TupleField{ obj: ref mut expr, .. } |
+        UnaryOp{ ref mut expr, .. } => v.visit_expr_mut(expr),
+        Struct{ ref mut ty, ref mut fields, ref mut base } => {
+            v.visit_ty_mut(ty);
+            for field in fields {
+                if let Some(ref mut expr) = field.expr {
+                    v.visit_expr_mut(expr);
+                }
+            }
+            if let Some(ref mut base) = *base {
+                v.visit_expr_mut(base);
+            }
+        },
+        Block{ ref mut attrs, ref mut stmts, ref mut ret } => {
+            for attr in attrs {
+                v.visit_attr_mut(attr);
+            }
+            for stmt in stmts {
+                v.visit_stmt_mut(stmt);
+            }
+            if let Some(ref mut ret) = *ret {
+                v.visit_expr_mut(ret);
+            }
+        },
+        MemberCall{ ref mut obj, ref mut func, ref mut args, .. } => {
+            v.visit_expr_mut(obj);
+            walk_path_comp_mut(v, func);
+            for arg in args {
+                v.visit_expr_mut(arg);
+            }
+        },
+        StructField{ ref mut obj, ref mut field } => {
+            v.visit_expr_mut(obj);
+            walk_path_comp_mut(v, field);
+        },
+        Index{ obj: ref mut l, index: ref mut r, .. } |
+        ArrayFill{ elem: ref mut l, len: ref mut r } |
+        BinaryOp{ ref mut l, ref mut r, .. } => {
+            v.visit_expr_mut(l);
+            v.visit_expr_mut(r);
+        },
+        Range{ ref mut start, ref mut end, .. } => {
+            if let Some(ref mut start) = *start {
+                v.visit_expr_mut(start);
+            }
+            if let Some(ref mut end) = *end {
+                v.visit_expr_mut(end);
+            }
+        },
+        As{ ref mut expr, ref mut ty, .. } |
+        Colon{ ref mut expr, ref mut ty, .. } => {
+            v.visit_expr_mut(expr);
+            v.visit_ty_mut(ty);
+        },
+        Call{ ref mut func, ref mut args, .. } => {
+            v.visit_expr_mut(func);
+            for arg in args {
+                v.visit_expr_mut(arg);
+            }
+        },
+        Lambda{ ref mut sig, ref mut body } => {
+            for arg in &mut sig.args {
+                v.visit_func_param_mut(arg);
+            }
+            if let Some(ref mut ty) = sig.ret_ty {
+                v.visit_ty_mut(ty);
+            }
+            v.visit_expr_mut(body);
+        },
+        Break{ ref mut expr, .. } | Return{ ref mut expr, .. } =>
+            if let Some(ref mut expr) = *expr {
+                v.visit_expr_mut(expr);
+            },
+        Loop{ ref mut body, .. } => v.visit_expr_mut(body),
+        While{ ref mut cond, ref mut body, .. } => {
+            v.visit_expr_mut(cond);
+            v.visit_expr_mut(body);
+        },
+        WhileLet{ ref mut pat, ref mut expr, ref mut body, .. } |
+        For{ ref mut pat, iter: ref mut expr, ref mut body, .. } => {
+            v.visit_pat_mut(pat);
+            v.visit_expr_mut(expr);
+            v.visit_expr_mut(body);
+        },
+        If{ ref mut cond, ref mut then_expr, ref mut else_expr } => {
+            v.visit_expr_mut(cond);
+            v.visit_expr_mut(then_expr);
+            if let Some(ref mut else_expr) = *else_expr {
+                v.visit_expr_mut(else_expr);
+            }
+        },
+        IfLet{
+            ref mut pat, ref mut match_expr, ref mut then_expr,
+            ref mut else_expr,
+        } => {
+            v.visit_pat_mut(pat);
+            v.visit_expr_mut(match_expr);
+            v.visit_expr_mut(then_expr);
+            if let Some(ref mut else_expr) = *else_expr {
+                v.visit_expr_mut(else_expr);
+            }
+        },
+        Match{ ref mut expr, ref mut arms, .. } => {
+            v.visit_expr_mut(expr);
+            for arm in arms {
+                v.visit_match_arm_mut(arm);
+            }
+        },
+        PluginInvoke(ref mut p) => v.visit_plugin_invoke_mut(p),
+    }
+}
+
+fn walk_path_comp_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:    &mut V,
+    comp: &mut PathComp<'a>,
+) {
+    if let PathComp::Name{ hint: Some(ref mut hint), .. } = *comp {
+        for arg in hint {
+            match *arg {
+                TyHintArg::Lifetime(_) => (),
+                TyHintArg::Ty(ref mut ty) => v.visit_ty_mut(ty),
+                TyHintArg::Const(ref mut e) => v.visit_expr_mut(e),
+            }
+        }
+    }
+}
+
+pub fn walk_match_arm_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    arm: &mut MatchArm<'a>,
+) {
+    for pat in &mut arm.pats {
+        v.visit_pat_mut(pat);
+    }
+    if let Some(ref mut cond) = arm.cond {
+        v.visit_expr_mut(cond);
+    }
+    v.visit_expr_mut(&mut arm.expr);
+}
+
+pub fn walk_pat_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    pat: &mut Pat<'a>,
+) {
+    match *pat {
+        Pat::Hole => (),
+        Pat::BindLike{ ref mut pat, .. } => if let Some(ref mut pat) = *pat {
+            v.visit_pat_mut(pat);
+        },
+        Pat::Path(ref mut path) => v.visit_path_mut(path),
+        Pat::Literal(ref mut lit) => v.visit_literal_mut(lit),
+        Pat::Range(ref mut l, ref mut r) => {
+            v.visit_literal_mut(l);
+            v.visit_literal_mut(r);
+        },
+        Pat::Ref{ ref mut pat, .. } | Pat::Paren(ref mut pat) =>
+            v.visit_pat_mut(pat),
+        Pat::Tuple(ref mut pats) | Pat::Or(ref mut pats) => for pat in pats {
+            v.visit_pat_mut(pat);
+        },
+        Pat::DestructTuple{ ref mut name, ref mut elems } => {
+            v.visit_path_mut(name);
+            for pat in elems {
+                v.visit_pat_mut(pat);
+            }
+        },
+        Pat::DestructNormal{ ref mut name, ref mut fields, .. } => {
+            v.visit_path_mut(name);
+            for field in fields {
+                if let Some(ref mut pat) = field.pat {
+                    v.visit_pat_mut(pat);
+                }
+            }
+        },
+        Pat::PluginInvoke(ref mut p) => v.visit_plugin_invoke_mut(p),
+    }
+}
+
+pub fn walk_literal_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v:   &mut V,
+    lit: &mut Literal<'a>,
+) {
+    match *lit {
+        Literal::IntLike{ ty: Some(ref mut ty), .. } |
+        Literal::FloatLike{ ty: Some(ref mut ty), .. } => v.visit_ty_mut(ty),
+        _ => (),
+    }
+}
+
+pub fn walk_plugin_invoke_mut<'a, V: VisitMut<'a> + ?Sized>(
+    v: &mut V,
+    p: &mut PluginInvoke<'a>,
+) {
+    v.visit_path_mut(&mut p.name);
+    v.visit_tt_mut(&mut p.tt);
+}
+
+pub fn walk_tt_mut<'a, V: VisitMut<'a> + ?Sized>(v: &mut V, tt: &mut TT<'a>) {
+    if let TTKind::Tree{ ref mut tts, .. } = tt.0 {
+        for tt in tts {
+            v.visit_tt_mut(tt);
+        }
+    }
+}
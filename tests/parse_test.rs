@@ -1,4 +1,5 @@
 extern crate mair;
+#[cfg(feature="serde")] extern crate serde_json;
 use std::fs::*;
 use std::path::{Path, PathBuf};
 use std::io::{self, Read, Write};
@@ -71,6 +72,14 @@ fn lex(input: &str) -> Result<Vec<Token>, LexicalError> {
     Ok(v)
 }
 
+fn lex_comments(input: &str) -> Result<Vec<Token>, LexicalError> {
+    let mut v = vec![];
+    for c in Lexer::with_comments(input) {
+        v.push(c?);
+    }
+    Ok(v)
+}
+
 fn tts(input: &str) -> Result<Vec<TT>, UnmatchedDelimError> {
     let ltoks = lex(input).unwrap();
     parse_tts(input, &ltoks)
@@ -81,11 +90,114 @@ fn parse(input: &str) -> (Mod, Vec<HardSyntaxError>) {
     parse_crate(input, tts_)
 }
 
+/// Print syntax errors with their positions in `s`.
+fn write_errs(f: &mut Write, s: &str, v: Vec<HardSyntaxError>) -> io::Result<()> {
+    for HardSyntaxError{ loc, reason } in v {
+        writeln!(f, "{}..{} {:?} {}",
+            str_ptr_diff(loc, s),
+            str_ptr_diff(&loc[loc.len()..], s),
+            loc,
+            reason,
+        )?;
+    }
+    Ok(())
+}
+
+fn write_parse(f: &mut Write, s: &str) -> io::Result<()> {
+    let (m, v) = parse(s);
+    writeln!(f, "{:?}", m)?;
+    write_errs(f, s, v)
+}
+
+fn write_parse_pat(f: &mut Write, s: &str) -> io::Result<()> {
+    let (pat, v) = parse_pat(s, tts(s).unwrap());
+    writeln!(f, "{:?}", pat)?;
+    writeln!(f, "{}", pat)?;
+    write_errs(f, s, v)
+}
+
+/// Parse a line like `ty: <ty>` with the entry point of the given kind.
+fn write_parse_entry(f: &mut Write, line: &str) -> io::Result<()> {
+    let colon = line.find(": ").unwrap();
+    let (kind, s) = (&line[..colon], &line[colon + 2..]);
+    let tts = tts(s).unwrap();
+    let v = match kind {
+        "ty" => {
+            let (ty, v) = parse_ty(s, tts);
+            writeln!(f, "{:?}", ty)?;
+            writeln!(f, "{}", ty)?;
+            v
+        },
+        "expr" => {
+            let (expr, v) = parse_expr(s, tts);
+            writeln!(f, "{:?}", expr)?;
+            writeln!(f, "{}", expr)?;
+            v
+        },
+        "item" => {
+            let (item, v) = parse_item(s, tts);
+            writeln!(f, "{:?}", item)?;
+            v
+        },
+        _ => panic!("Unknown entry `{}`", kind),
+    };
+    write_errs(f, s, v)
+}
+
+/// Print attribute queries of each item.
+fn write_attr_query(f: &mut Write, s: &str) -> io::Result<()> {
+    let (m, _) = parse(s);
+    for item in &m.items {
+        let docs: Vec<_> = item.attrs.iter().filter_map(Attr::doc).collect();
+        writeln!(f, "docs: {:?}", docs)?;
+        for (pred, metas) in item.attrs.iter().filter_map(Attr::as_cfg_attr) {
+            let metas: Vec<_> = metas.iter().map(|m| m.to_string()).collect();
+            writeln!(f, "cfg_attr: {} => {:?}", pred, metas)?;
+        }
+        let derives: Vec<_> = item.derives().iter()
+                                  .map(|p| p.to_string()).collect();
+        writeln!(f, "derives: {:?}", derives)?;
+        if let Some(path) = item.mod_path_override() {
+            writeln!(f, "path: {:?}", path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Print the type in `type T = <ty>;` and check whether it is parsed back to
+/// the same type.
+fn write_print_ty(f: &mut Write, s: &str) -> io::Result<()> {
+    fn ty_of(m: Mod) -> Option<Ty> {
+        match m.items.into_iter().next().map(|item| item.detail) {
+            Some(ItemKind::Type{ origin, .. }) => Some(*origin),
+            _ => None,
+        }
+    }
+    let src = format!("type T = {};", s);
+    let ty = ty_of(parse(&src).0).unwrap();
+    let printed = format!("{}", ty);
+    let src2 = format!("type T = {};", printed);
+    let ty2 = ty_of(parse(&src2).0);
+    writeln!(f, "{} {}", printed, ty2 == Some(ty))
+}
+
+/// Print the module and check whether printing it again after parsing gives
+/// the same source.
+fn write_print_mod(f: &mut Write, s: &str) -> io::Result<()> {
+    let printed = format!("{}", parse(s).0);
+    let printed2 = format!("{}", parse(&printed).0);
+    write!(f, "{}", printed)?;
+    writeln!(f, "{}", printed == printed2)
+}
+
 #[test]
 fn parse_test() {
     test_dir_lines("lexer_unit", |f, s| {
         writeln!(f, "{:?}", lex(s))
     });
+    test_dir_lines("lexer_comment", |f, s| {
+        writeln!(f, "{:?}", lex_comments(s))
+    });
     test_dir("lexer_large", |f, s| {
         writeln!(f, "{:?}", lex(s))
     });
@@ -95,17 +207,242 @@ fn parse_test() {
     test_dir("tts_large", |f, s| {
         writeln!(f, "{:?}", tts(s))
     });
-    test_dir("parser_large", |f, s| {
-        let (m, v) = parse(s);
-        writeln!(f, "{:?}", m)?;
-        for HardSyntaxError{ loc, reason } in v {
-            writeln!(f, "{}..{} {:?} {}",
-                str_ptr_diff(loc, s),
-                str_ptr_diff(&loc[loc.len()..], s),
-                loc,
-                reason,
-            )?;
+    test_dir_lines("parser_unit", write_parse);
+    test_dir_lines("parser_pat", write_parse_pat);
+    test_dir_lines("parser_entry", write_parse_entry);
+    test_dir_lines("attr_query", write_attr_query);
+    test_dir_lines("print_ty", write_print_ty);
+    test_dir("print_mod", write_print_mod);
+    test_dir("parser_large", write_parse);
+}
+
+#[test]
+fn parse_error_test() {
+    let s = "fn f() {\n    let x = (1, 2;\n}\n";
+    let e = parse_source(s).unwrap_err();
+    assert_eq!(e.line_col(s), (3, 1));
+    assert_eq!(e.snippet(s).to_string(), concat!(
+        "3:1: Expect `)` to close `(` at 2:13, found `}`\n",
+        "}\n",
+        "^\n",
+    ));
+    assert_eq!(e.expected.as_ref().map(|s| &s[..]), Some("`)`"));
+
+    let s = "fn f() {
+    g(1;
+";
+    let e = parse_source(s).unwrap_err();
+    assert_eq!(e.line_col(s), (2, 6));
+    assert_eq!(e.to_string(), "Unclosed delimiter, found `(`");
+
+    let s = "fn f() {}
+}";
+    let e = parse_source(s).unwrap_err();
+    assert_eq!(e.line_col(s), (2, 1));
+    assert_eq!(e.to_string(), "Unexpected close delimiter, found `}`");
+
+    let s = "struct S {\n    a: i32\n    b: i32,\n}\n";
+    let (_, errs) = parse_source(s).unwrap();
+    let e = &errs[0];
+    assert_eq!(e.line_col(s), (3, 5));
+    assert_eq!(e.expected.as_ref().map(|s| &s[..]), Some("a separator"));
+    assert_eq!(e.found.as_ref().map(|s| &s[..]), Some("b"));
+
+    let s = "let a = \"ab";
+    let e = parse_source(s).unwrap_err();
+    assert_eq!(e.line_col(s), (1, 9));
+}
+
+#[test]
+fn macro_body_source_test() {
+    let s = "m!{ a  /* b */\n  c }\nmacro_rules! n { ($x:expr) => { $x }; }";
+    let (m, errs) = parse_source(s).unwrap();
+    assert!(errs.is_empty());
+    match m.items[0].detail {
+        ItemKind::PluginInvoke(ref p) =>
+            assert_eq!(p.body_source(), " a  /* b */\n  c "),
+        _ => unreachable!(),
+    }
+    match m.items[1].detail {
+        ItemKind::MacroDef{ ref body, .. } => {
+            let sp = Span::of(s, body.1);
+            assert_eq!(&s[sp.start..sp.end], "{ ($x:expr) => { $x }; }");
+        },
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn edition_test() {
+    use mair::parse::lexer::Edition;
+    let s = "async fn f(x: &dyn T) -> Box<dyn T + 'static> { x }";
+    let (m15, errs) = parse_source_with_edition(s, Edition::Rust2015).unwrap();
+    assert!(errs.is_empty());
+    let (m18, errs) = parse_source_with_edition(s, Edition::Rust2018).unwrap();
+    assert!(errs.is_empty());
+    assert_eq!(m15, m18);
+
+    let s = "fn f() { a().await.b().await?; x.await }";
+    let (m, errs) = parse_source_with_edition(s, Edition::Rust2018).unwrap();
+    assert!(errs.is_empty());
+    assert_eq!(m.to_string(), concat!(
+        "fn f() {\n",
+        "    a().await.b().await?;\n",
+        "    x.await\n",
+        "}\n",
+    ));
+    let stmt = match m.items[0].detail {
+        ItemKind::Func{ ref body, .. } => match **body {
+            Expr::Block{ ref stmts, .. } => stmts[0].clone(),
+            _ => unreachable!(),
+        },
+        _ => unreachable!(),
+    };
+    match stmt {
+        Stmt::Semi(Expr::UnaryOp{ op: UnaryOp::Try, ref expr, .. }) =>
+            match **expr {
+                Expr::Await(ref e) => match **e {
+                    Expr::MemberCall{ ref obj, .. } => match **obj {
+                        Expr::Await(_) => (),
+                        ref e => panic!("{:?}", e),
+                    },
+                    ref e => panic!("{:?}", e),
+                },
+                ref e => panic!("{:?}", e),
+            },
+        ref s => panic!("{:?}", s),
+    }
+    let (m, _) = parse_source_with_edition(s, Edition::Rust2015).unwrap();
+    assert!(format!("{:?}", m).contains("StructField"));
+
+    let s = "fn f() { let x: Option<u8> = try { a? + 1 }; async move {} }";
+    let (m, errs) = parse_source_with_edition(s, Edition::Rust2018).unwrap();
+    assert!(errs.is_empty());
+    assert_eq!(m.to_string(), concat!(
+        "fn f() {\n",
+        "    let x: Option<u8> = try {\n",
+        "        a? + 1\n",
+        "    };\n",
+        "    async move {}\n",
+        "}\n",
+    ));
+
+    let s = "let async = 1;";
+    let kinds = |e| Lexer::new(s).edition(e)
+                        .map(|t| t.unwrap().0).collect::<Vec<_>>();
+    assert_eq!(kinds(Edition::Rust2015)[1], TokenKind::Ident("async"));
+    assert_eq!(kinds(Edition::Rust2018)[1],
+               TokenKind::Keyword(KeywordType::Async));
+}
+
+#[test]
+fn whitespace_round_trip_test() {
+    use mair::parse::Span;
+    for dir in &["lexer_large", "parser_large"] {
+        for dirent in read_dir(Path::new("tests").join(dir)).unwrap() {
+            let path = dirent.unwrap().path();
+            if path.extension() != Some(OsStr::new("in")) {
+                continue;
+            }
+            let mut s = String::new();
+            File::open(&path).unwrap().read_to_string(&mut s).unwrap();
+            let mut end = 0;
+            for tok in SpannedLexer::with_whitespaces(&s) {
+                let ((_, loc), span) = tok.unwrap();
+                assert_eq!(span, Span{ start: end, end: end + loc.len() });
+                end = span.end;
+            }
+            assert_eq!(end, s.len(), "{}", path.display());
         }
-        Ok(())
-    });
+    }
+}
+
+#[test]
+fn eval_const_int_test() {
+    use std::collections::HashMap;
+    use mair::parse::eval::eval_const_int;
+    let mut env = HashMap::new();
+    env.insert("N", 8);
+    let eval = |s: &str| {
+        let (expr, errs) = parse_expr(s, tts(s).unwrap());
+        assert!(errs.is_empty());
+        eval_const_int(&expr, &env)
+    };
+    assert_eq!(eval("3 * (4 + 1)"), Some(15));
+    assert_eq!(eval("1 << 4 | 1"), Some(17));
+    assert_eq!(eval("-(2 - N) % 4"), Some(2));
+    assert_eq!(eval("!0 ^ { N / 3 }"), Some(!2));
+    assert_eq!(eval("N / 0"), None);
+    assert_eq!(eval("M + 1"), None);
+    assert_eq!(eval("1 << 64"), None);
+    assert_eq!(eval("1 << 63"), None);
+    assert_eq!(eval("3 << 62"), None);
+    assert_eq!(eval("-1 << 63"), Some(i64::min_value()));
+    assert_eq!(eval("-8 >> 1"), Some(-4));
+    assert_eq!(eval("9223372036854775808"), None);
+    assert_eq!(eval("340282366920938463463374607431768211455u128"), None);
+    assert_eq!(eval("-9223372036854775808"), Some(i64::min_value()));
+    assert_eq!(eval("f(1)"), None);
+}
+
+#[cfg(feature="proc-macro2")]
+#[test]
+fn proc_macro_round_trip_test() {
+    use mair::parse::proc_macro::*;
+    let s = "impl<T: Tr<Vec<u8>>> S<'a> { x >>= -1; 'a: loop {} }";
+    let ts = to_token_stream(&tts(s).unwrap());
+    let toks = from_token_stream(&ts).unwrap();
+    let expected = lex(s).unwrap();
+    assert_eq!(toks.tokens().len(), expected.len());
+    for (tok, tok_expected) in toks.tokens().iter().zip(&expected) {
+        assert_eq!(tok.0, tok_expected.0);
+    }
+    let tts2 = parse_tts(toks.source(), toks.tokens()).unwrap();
+    let ts2 = to_token_stream(&tts2);
+    assert_eq!(ts2.to_string(), ts.to_string());
+}
+
+#[test]
+fn line_index_test() {
+    use mair::parse::{LineIndex, ColumnUnit};
+    let sources = [
+        "",
+        "\n\r\n\n",
+        "fn main() {\r\n    let 名字 = \"🦀\";\r\n}",
+        "é\r\n\r\nà🦀b\n🦀\n",
+    ];
+    for s in &sources {
+        let by_char = LineIndex::new(s, ColumnUnit::Char);
+        let by_utf16 = LineIndex::new(s, ColumnUnit::Utf16);
+        for offset in (0..s.len() + 1).filter(|&i| s.is_char_boundary(i)) {
+            let before = &s[..offset];
+            let line_begin = before.rfind('\n').map_or(0, |i| i + 1);
+            let line = before.matches('\n').count() as u32 + 1;
+            let cols = &before[line_begin..];
+            assert_eq!(by_char.offset_to_pos(offset),
+                       (line, cols.chars().count() as u32 + 1));
+            assert_eq!(by_utf16.offset_to_pos(offset),
+                       (line, cols.encode_utf16().count() as u32 + 1));
+        }
+    }
+}
+
+#[cfg(feature="serde")]
+#[test]
+fn serde_json_test() {
+    let s = "pub struct S { a: u8 } fn f() -> i32 { 1 }";
+    let m = parse(s).0;
+    let json = serde_json::to_value(&m).unwrap();
+    let item = &json["items"][0];
+    assert_eq!(item["vis"], "Public");
+    assert_eq!(item["span"], serde_json::json!({ "start": 0, "end": 22 }));
+    let field = &item["detail"]["StructFields"]["fields"][0];
+    assert_eq!(field["name"]["Ok"], "a");
+    let ty_name = &field["ty"]["Apply"]["Angle"]["name"]["comps"][0]["Name"];
+    assert_eq!(ty_name["name"]["Ok"], "u8");
+    let body = &json["items"][1]["detail"]["Func"]["body"]["Block"];
+    assert_eq!(body["ret"]["Literal"]["IntLike"]["val"], 1);
+
+    let json = serde_json::to_string(&m).unwrap();
+    assert_eq!(serde_json::from_str::<Mod>(&json).unwrap(), m);
 }